@@ -1,16 +1,41 @@
 use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
-use anyhow::{Result, Context};
+use anyhow::{Result, Context, bail};
 use log::{warn, info, debug};
 use rayon::prelude::*;
+use regex::RegexSet;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
 
-use crate::class::types::{ProcessedClass, ClassScanStats, ClassScanOptions, ClassScanResult, ScanErrors};
+use crate::class::types::{ProcessedClass, ClassScanStats, ClassScanOptions, ClassScanResult, ScanErrors, ScanAuditRecord, DefineConflict, FileBench, SourceKind, TRUNCATION_MARKER, merge_declarations, resolve_duplicates};
+use crate::utils::file_utils;
 use crate::class::scanner::simple_parser::{SimpleParser, ClassBlock};
-use crate::class::scanner::FileCollector;
+use crate::class::scanner::{ClassExtractor, FileCollector};
+
+/// Everything extracted from a single successfully parsed file
+struct ParsedFile {
+    /// Classes found in the file
+    classes: Vec<ClassBlock>,
+    
+    /// Hash of the (possibly normalized) file content
+    hash: String,
+    
+    /// `#define` constants, when collection is enabled
+    defines: Vec<(String, String)>,
+}
+
+/// Classes parsed during a run, keyed by content hash. Each slot has its own
+/// lock so concurrent files with the same content wait for a single parse.
+type ParseCache = Mutex<HashMap<String, Arc<Mutex<Option<Vec<ClassBlock>>>>>>;
 
 /// Class processor responsible for collecting parsed classes
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ClassProcessor {
     /// Configuration options for processing
     options: ClassScanOptions,
@@ -18,14 +43,20 @@ pub struct ClassProcessor {
     /// Output directory for logs and temporary files
     output_dir: PathBuf,
     
-    /// Simple parser for extracting class definitions
+    /// Simple parser, used for preprocessor directives
     parser: SimpleParser,
     
+    /// Extracts class definitions; the simple parser unless replaced with `with_parser`
+    extractor: Arc<dyn ClassExtractor>,
+    
     /// File collector for finding class files
     file_collector: FileCollector,
     
     /// Error tracking
     scan_errors: ScanErrors,
+    
+    /// Optional run-level audit log, one JSON line per scan
+    audit_log: Option<PathBuf>,
 }
 
 impl ClassProcessor {
@@ -34,9 +65,11 @@ impl ClassProcessor {
         Self {
             options: options.clone(),
             output_dir: output_dir.as_ref().to_path_buf(),
-            parser: SimpleParser::new(options.verbose_errors),
+            parser: SimpleParser::from_options(&options),
+            extractor: Arc::new(SimpleParser::from_options(&options)),
             file_collector: FileCollector::new(),
             scan_errors: ScanErrors::default(),
+            audit_log: None,
         }
     }
     
@@ -45,9 +78,68 @@ impl ClassProcessor {
         Self::new(ClassScanOptions::default(), output_dir)
     }
     
+    /// Extract classes with a different parser, such as a slower but more
+    /// accurate one for critical runs
+    pub fn with_parser(mut self, extractor: Box<dyn ClassExtractor>) -> Self {
+        self.extractor = Arc::from(extractor);
+        self
+    }
+    
+    /// Append a JSON line describing each `process_files` run to the given file
+    pub fn with_audit_log(mut self, path: impl AsRef<Path>) -> Self {
+        self.audit_log = Some(path.as_ref().to_path_buf());
+        self
+    }
+    
     /// Process files and return the results
     pub fn process_files(&mut self, files: &[PathBuf]) -> Result<ClassScanResult> {
+        self.process_files_cancellable(files, Arc::new(AtomicBool::new(false)))
+    }
+    
+    /// Parse each file in turn on the current thread, timing how long each one
+    /// takes. Nothing is merged, stored or reported, so the timings only cover
+    /// the per-file work of a scan.
+    pub fn benchmark_files(&self, files: &[PathBuf]) -> Vec<FileBench> {
+        files.iter()
+            .map(|file| {
+                let start = Instant::now();
+                let parsed = self.parse_file(file, None);
+                let duration = start.elapsed();
+                
+                let classes = match parsed {
+                    Ok(parsed) => parsed.classes.len(),
+                    Err(e) => {
+                        debug!("Failed to parse {} while benchmarking: {}", file.display(), e);
+                        0
+                    }
+                };
+                FileBench { path: file.clone(), duration, classes }
+            })
+            .collect()
+    }
+    
+    /// Process files, stopping early once `cancel` is set.
+    ///
+    /// Files already parsed when the flag is raised are kept, so the result
+    /// holds partial data. `cancelled` is set only if files were skipped.
+    pub fn process_files_cancellable(&mut self, files: &[PathBuf], cancel: Arc<AtomicBool>) -> Result<ClassScanResult> {
         info!("Processing {} files", files.len());
+        self.ensure_output_dir()?;
+        
+        // Patterns must match the whole class name
+        let blacklist = RegexSet::new(self.options.blacklist_patterns.iter().map(|p| format!("^(?:{})$", p)))
+            .context("Invalid class blacklist pattern")?;
+        
+        // Draw a random sample of the files if configured
+        let sampled;
+        let files = match self.options.sample_files {
+            Some(count) if files.len() > count => {
+                info!("Sampling {} files out of {}", count, files.len());
+                sampled = sample_files(files, count, self.options.sample_seed);
+                &sampled[..]
+            }
+            _ => files,
+        };
         
         // Limit the number of files if configured
         let files_to_process = if let Some(max_files) = self.options.max_files {
@@ -74,25 +166,37 @@ impl ClassProcessor {
             .build_global()
             .unwrap_or_else(|e| warn!("Failed to build thread pool: {}", e));
         
+        let parse_cache = self.options.cache_parsed_content.then(ParseCache::default);
+        
         // Thread-safe collection of error files
         let error_files = Arc::new(Mutex::new(Vec::new()));
         let timeout_files = Arc::new(Mutex::new(Vec::new()));
         
         // Process files in parallel
         let results: Vec<_> = files_to_process.par_iter()
-            .map(|file| {
-                match self.parser.parse_file(file) {
-                    Ok(classes) => (file.clone(), classes, true, None),
+            .filter_map(|file| {
+                if cancel.load(Ordering::Relaxed) {
+                    return None;
+                }
+                
+                match self.parse_file(file, parse_cache.as_ref()) {
+                    Ok(parsed) => Some((file.clone(), Some(parsed))),
                     Err(e) => {
                         warn!("Failed to parse file {}: {}", file.display(), e);
                         // Thread-safe update of error files
                         error_files.lock().unwrap().push(file.clone());
-                        (file.clone(), Vec::new(), false, Some(e.to_string()))
+                        Some((file.clone(), None))
                     }
                 }
             })
             .collect();
         
+        // A flag raised after the last file started doesn't lose anything
+        let cancelled = results.len() < files_to_process.len();
+        if cancelled {
+            warn!("Scan cancelled after {} of {} files", results.len(), files_to_process.len());
+        }
+        
         // Update the scan errors after parallel processing
         self.scan_errors.error_files = error_files.lock().unwrap().clone();
         self.scan_errors.timeout_files = timeout_files.lock().unwrap().clone();
@@ -100,6 +204,10 @@ impl ClassProcessor {
         // Calculate statistics and convert to processed classes
         let mut stats = ClassScanStats::default();
         let mut all_classes = Vec::new();
+        let mut file_hashes = HashMap::new();
+        let mut suspicious_files = Vec::new();
+        let mut define_sources: HashMap<String, Vec<(PathBuf, String)>> = HashMap::new();
+        let mut defines = HashMap::new();
         
         stats.total_files = results.len();
         stats.error_files = self.scan_errors.error_files.len();
@@ -107,37 +215,131 @@ impl ClassProcessor {
         stats.timeout_files = self.scan_errors.timeout_files.len();
         stats.timeout_file_paths = self.scan_errors.timeout_files.clone();
         
-        for (file, classes, success, error) in results {
-            if !success {
+        for (file, parsed) in results {
+            let extension = file.extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let extension_stats = stats.by_extension.entry(extension).or_default();
+            extension_stats.files += 1;
+            
+            let Some(ParsedFile { classes, hash, defines: file_defines }) = parsed else {
+                extension_stats.errors += 1;
                 continue;
+            };
+            file_hashes.insert(self.stored_path(&file), hash);
+            
+            for (name, value) in file_defines {
+                defines.entry(name.clone()).or_insert_with(|| value.clone());
+                define_sources.entry(name).or_default().push((file.clone(), value));
+            }
+            
+            if let Some(threshold) = self.options.warn_below_classes
+                && classes.len() < threshold {
+                warn!("File {} produced only {} classes (expected at least {})",
+                    file.display(), classes.len(), threshold);
+                suspicious_files.push(file.clone());
             }
             
             if classes.is_empty() {
                 stats.empty_files += 1;
+                stats.empty_file_paths.push(file.clone());
                 continue;
             }
             
-            stats.files_with_classes += 1;
-            stats.total_classes += classes.len();
+            // Drop names too short to be real classes
+            let found = classes.len();
+            let classes: Vec<ClassBlock> = classes.into_iter()
+                .filter(|class| class.name.chars().count() >= self.options.min_class_name_len)
+                .collect();
+            stats.filtered += found - classes.len();
             
-            // Convert ClassBlock to ProcessedClass
+            // Convert ClassBlock to ProcessedClass, dropping blacklisted names
+            let found = classes.len();
             let processed_classes: Vec<ProcessedClass> = classes.into_iter()
-                .map(|class| ProcessedClass {
-                    name: class.name,
-                    parent: class.parent,
-                    properties: Vec::new(), // No properties in simplified version
-                    file_path: Some(class.file_path),
-                })
+                .filter(|class| !blacklist.is_match(&class.name))
+                .map(|class| self.to_processed_class(class))
                 .collect();
+            stats.blacklisted += found - processed_classes.len();
+            stats.total_classes += processed_classes.len();
+            
+            // Files whose every class was filtered out don't count as having classes
+            if !processed_classes.is_empty() {
+                stats.files_with_classes += 1;
+            }
             
             all_classes.extend(processed_classes);
         }
         
-        info!("Processed {} files, found {} classes", stats.total_files, stats.total_classes);
+        if !self.options.exclude_parents.is_empty() {
+            let before = all_classes.len();
+            all_classes = self.exclude_by_parent(all_classes);
+            stats.excluded_classes = before - all_classes.len();
+            stats.total_classes -= stats.excluded_classes;
+            debug!("Excluded {} classes by parent", stats.excluded_classes);
+        }
+        
+        let before = all_classes.len();
+        all_classes = merge_declarations(all_classes, self.options.merge_declarations_across_files);
+        stats.merged_declarations = before - all_classes.len();
+        stats.total_classes -= stats.merged_declarations;
+        
+        let (deduplicated, class_conflicts) = resolve_duplicates(all_classes, self.options.deduplicate_identical);
+        all_classes = deduplicated;
+        if self.options.deduplicate_identical {
+            stats.duplicate_classes = stats.total_classes - all_classes.len();
+            stats.total_classes = all_classes.len();
+            debug!("Dropped {} identical duplicate classes", stats.duplicate_classes);
+        }
+        for conflict in &class_conflicts {
+            warn!("Class {} is defined {} times with differing content", conflict.name, conflict.files.len());
+        }
+        
+        // Count classes per extension only once every class that will be
+        // dropped is gone, so the counts match the classes returned
+        stats.recount_classes_by_extension(&all_classes);
+        
+        if self.options.resolve_parents {
+            resolve_parent_files(&mut all_classes);
+        }
+        
+        // Defines with more than one distinct value across the scan are conflicts
+        let mut define_conflicts: Vec<DefineConflict> = define_sources.into_iter()
+            .filter(|(_, sources)| sources.iter().any(|(_, value)| *value != sources[0].1))
+            .map(|(name, definitions)| DefineConflict { name, definitions })
+            .collect();
+        define_conflicts.sort_by(|a, b| a.name.cmp(&b.name));
+        for conflict in &define_conflicts {
+            warn!("Macro {} is defined with {} different values", conflict.name, conflict.definitions.len());
+        }
+        
+        info!("Processed {} files, found {} classes ({:.1}% success, {:.1} classes per file)",
+            stats.total_files, stats.total_classes,
+            stats.success_rate() * 100.0, stats.avg_classes_per_file());
+        
+        if let Some(audit_log) = &self.audit_log {
+            self.write_audit_record(audit_log, &stats)?;
+        }
+        
+        if let Some(max_error_rate) = self.options.max_error_rate {
+            let error_rate = 1.0 - stats.success_rate();
+            if error_rate > max_error_rate {
+                bail!("{} of {} files failed to parse ({:.1}%), above the maximum error rate of {:.1}%",
+                    stats.total_files - stats.successful_files(), stats.total_files,
+                    error_rate * 100.0, max_error_rate * 100.0);
+            }
+        }
         
         Ok(ClassScanResult {
             classes: all_classes,
             stats,
+            file_hashes,
+            hash_algorithm: self.options.hash_algorithm,
+            suspicious_files,
+            defines,
+            define_conflicts,
+            class_conflicts,
+            cancelled,
+            path_root: self.options.path_root.clone(),
         })
     }
     
@@ -153,14 +355,224 @@ impl ClassProcessor {
         self.process_files(&files)
     }
     
+    /// Scan a directory on tokio's blocking thread pool, keeping the async
+    /// runtime free while the files are parsed
+    #[cfg(feature = "async")]
+    pub async fn scan_directory_async(&mut self, input_dir: impl AsRef<Path>) -> Result<ClassScanResult> {
+        let input_dir = input_dir.as_ref().to_path_buf();
+        let mut processor = self.clone();
+        
+        let (processor, result) = tokio::task::spawn_blocking(move || {
+            let result = processor.scan_directory(&input_dir);
+            (processor, result)
+        })
+        .await
+        .context("Scan task failed to complete")?;
+        
+        self.scan_errors = processor.scan_errors;
+        result
+    }
+    
     /// Scan specific files for classes
     pub fn scan_specific_files(&mut self, file_paths: &[PathBuf]) -> Result<ClassScanResult> {
         info!("Scanning {} specific files", file_paths.len());
         self.process_files(file_paths)
     }
     
+    /// Create the output directory if it doesn't exist yet
+    pub fn ensure_output_dir(&self) -> Result<()> {
+        file_utils::ensure_dir_exists(&self.output_dir)
+            .with_context(|| format!("Output directory {} is not usable", self.output_dir.display()))
+    }
+    
     /// Get the scan errors
     pub fn get_scan_errors(&self) -> &ScanErrors {
         &self.scan_errors
     }
-} 
\ No newline at end of file
+    
+    /// Read, parse and hash a single file, reusing the classes of earlier
+    /// files with the same content when a cache is given
+    fn parse_file(&self, file: &Path, cache: Option<&ParseCache>) -> Result<ParsedFile> {
+        let content = file_utils::read_file_to_string_with_retry(file, self.options.read_retries)?;
+        let content = if self.options.normalize_line_endings {
+            file_utils::normalize_line_endings(&content).into_owned()
+        } else {
+            content
+        };
+        
+        let hash = self.options.hash_algorithm.hash_string(&content);
+        let defines = if self.options.collect_defines {
+            self.parser.parse_defines(&content)
+        } else {
+            Vec::new()
+        };
+        let classes = match cache {
+            Some(cache) => {
+                let slot = cache.lock().unwrap().entry(hash.clone()).or_default().clone();
+                let mut slot = slot.lock().unwrap();
+                match slot.as_ref() {
+                    Some(cached) => {
+                        debug!("Reusing parsed classes for {} from identical content", file.display());
+                        cached.iter()
+                            .cloned()
+                            .map(|mut class| {
+                                class.file_path = file.to_path_buf();
+                                class
+                            })
+                            .collect()
+                    }
+                    None => {
+                        let classes = self.extractor.extract(&content, file)?;
+                        *slot = Some(classes.clone());
+                        classes
+                    }
+                }
+            }
+            None => self.extractor.extract(&content, file)?,
+        };
+        
+        Ok(ParsedFile { classes, hash, defines })
+    }
+    
+    /// Get the path stored for a file's classes and hash, relative to
+    /// `path_root` when the file is inside it
+    pub fn stored_path(&self, file: &Path) -> PathBuf {
+        self.options.path_root.as_deref()
+            .and_then(|root| file.strip_prefix(root).ok())
+            .unwrap_or(file)
+            .to_path_buf()
+    }
+    
+    /// Convert a parsed class block into a processed class, applying value limits
+    fn to_processed_class(&self, class: ClassBlock) -> ProcessedClass {
+        let mut truncated_properties = Vec::new();
+        let properties = class.properties.into_iter()
+            .map(|(name, value)| {
+                let value = match self.options.max_property_value_len {
+                    Some(max_len) if value.chars().count() > max_len => {
+                        truncated_properties.push(name.clone());
+                        let cut: String = value.chars().take(max_len).collect();
+                        cut + TRUNCATION_MARKER
+                    }
+                    _ => value,
+                };
+                (name, value.into())
+            })
+            .collect();
+        
+        ProcessedClass {
+            name: class.name,
+            parent: class.parent,
+            properties,
+            container_path: class.container_path,
+            is_forward_declaration: !class.is_definition,
+            truncated_properties,
+            quoted_properties: class.quoted_properties,
+            annotations: class.annotations,
+            source_kind: SourceKind::from_path(&class.file_path),
+            file_path: Some(self.stored_path(&class.file_path)),
+            resolved_parent: None,
+        }
+    }
+    
+    /// Drop classes descending from `exclude_parents`, through inheritance or nesting
+    fn exclude_by_parent(&self, classes: Vec<ProcessedClass>) -> Vec<ProcessedClass> {
+        // Classes by parent name, and by every scope enclosing them as
+        // (file, container path prefix), so nesting below a class that was
+        // filtered out earlier is still found
+        let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut nested: HashMap<(Option<&Path>, &[String]), Vec<usize>> = HashMap::new();
+        for (i, class) in classes.iter().enumerate() {
+            if let Some(parent) = class.parent.as_deref() {
+                children.entry(parent).or_default().push(i);
+            }
+            for len in 1..=class.container_path.len() {
+                nested.entry((class.file_path.as_deref(), &class.container_path[..len])).or_default().push(i);
+            }
+        }
+        
+        let mut excluded = vec![false; classes.len()];
+        let mut pending: Vec<usize> = self.options.exclude_parents.iter()
+            .filter_map(|parent| children.get(parent.as_str()))
+            .flatten()
+            .copied()
+            .collect();
+        while let Some(i) = pending.pop() {
+            if excluded[i] {
+                continue;
+            }
+            excluded[i] = true;
+            
+            let class = &classes[i];
+            if let Some(inheriting) = children.get(class.name.as_str()) {
+                pending.extend(inheriting);
+            }
+            let mut scope = class.container_path.clone();
+            scope.push(class.name.clone());
+            if let Some(inside) = nested.get(&(class.file_path.as_deref(), scope.as_slice())) {
+                pending.extend(inside);
+            }
+        }
+        
+        classes.into_iter()
+            .zip(excluded)
+            .filter(|(_, excluded)| !excluded)
+            .map(|(class, _)| class)
+            .collect()
+    }
+    
+    /// Append a record of a finished scan to the audit log
+    fn write_audit_record(&self, audit_log: &Path, stats: &ClassScanStats) -> Result<()> {
+        let record = ScanAuditRecord {
+            timestamp: chrono::Utc::now(),
+            total_files: stats.total_files,
+            total_classes: stats.total_classes,
+            error_files: stats.error_files,
+            timeout_files: stats.timeout_files,
+        };
+        
+        let line = serde_json::to_string(&record)
+            .context("Failed to serialize scan audit record")?;
+        
+        file_utils::append_line_to_file(audit_log, &line)
+            .with_context(|| format!("Failed to write scan audit log {}", audit_log.display()))
+    }
+}
+
+/// Point each class's `resolved_parent` at the file defining its parent,
+/// preferring a definition in the same file, then one in the same container.
+/// A parent that is only forward-declared in the scan is external, so it
+/// stays unresolved.
+fn resolve_parent_files(classes: &mut [ProcessedClass]) {
+    let mut by_name: HashMap<&str, Vec<&ProcessedClass>> = HashMap::new();
+    for class in classes.iter().filter(|class| !class.is_forward_declaration) {
+        by_name.entry(class.name.as_str()).or_default().push(class);
+    }
+    
+    let resolved: Vec<Option<PathBuf>> = classes.iter()
+        .map(|class| {
+            let candidates = by_name.get(class.parent.as_deref()?)?;
+            let best = candidates.iter().find(|parent| parent.file_path == class.file_path)
+                .or_else(|| candidates.iter().find(|parent| parent.container_path == class.container_path))
+                .unwrap_or(&candidates[0]);
+            best.file_path.clone()
+        })
+        .collect();
+    
+    for (class, parent_file) in classes.iter_mut().zip(resolved) {
+        class.resolved_parent = parent_file;
+    }
+}
+
+/// Pick `count` files at random with reservoir sampling, keeping their original
+/// order. The same seed always picks the same files.
+fn sample_files(files: &[PathBuf], count: usize, seed: Option<u64>) -> Vec<PathBuf> {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    
+    let mut indices = (0..files.len()).choose_multiple(&mut rng, count);
+    indices.sort_unstable();
+    indices.into_iter().map(|i| files[i].clone()).collect()
+}
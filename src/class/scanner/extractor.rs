@@ -0,0 +1,18 @@
+use std::fmt::Debug;
+use std::path::Path;
+use anyhow::Result;
+
+use super::simple_parser::{SimpleParser, ClassBlock};
+
+/// A source of class definitions, letting `ClassProcessor` run with a parser
+/// other than the regex-based `SimpleParser`
+pub trait ClassExtractor: Debug + Send + Sync {
+    /// Extract the classes defined in `content`, read from `path`
+    fn extract(&self, content: &str, path: &Path) -> Result<Vec<ClassBlock>>;
+}
+
+impl ClassExtractor for SimpleParser {
+    fn extract(&self, content: &str, path: &Path) -> Result<Vec<ClassBlock>> {
+        self.parse_content(content.to_string(), path)
+    }
+}
@@ -1,11 +1,34 @@
 use std::path::{Path, PathBuf};
-use anyhow::Result;
+use anyhow::{Result, Context};
 use log::{debug, trace};
 
 use crate::utils::file_utils;
 
+/// Files found by a collection walk, with the paths it couldn't read
+#[derive(Debug, Clone, Default)]
+pub struct FileCollection {
+    /// Files with valid extensions
+    pub files: Vec<PathBuf>,
+    
+    /// Paths that errored during the walk, with the reason; a directory listed
+    /// here means its whole subtree was skipped
+    pub collection_errors: Vec<(PathBuf, String)>,
+}
+
+/// A config file read from inside an archive
+#[cfg(feature = "zip")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveFile {
+    /// The archive path joined with the file's path inside it, e.g.
+    /// `release.zip/addons/main/config.cpp`. Used to label the file's classes.
+    pub path: PathBuf,
+    
+    /// The file's content, with any UTF-8 BOM removed
+    pub content: String,
+}
+
 /// File collector for finding class files
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct FileCollector {
     /// Valid file extensions to collect
     valid_extensions: Vec<String>,
@@ -19,6 +42,13 @@ impl FileCollector {
         }
     }
     
+    /// Create a file collector for every common Arma config extension:
+    /// `cpp`, `hpp`, `h`, `inc`, `ext` (e.g. `description.ext`) and `sqm`
+    /// mission files
+    pub fn arma_defaults() -> Self {
+        Self::with_extensions(["cpp", "hpp", "h", "inc", "ext", "sqm"].iter().map(|ext| ext.to_string()).collect())
+    }
+    
     /// Create a new file collector with custom file extensions
     pub fn with_extensions(extensions: Vec<String>) -> Self {
         Self {
@@ -26,6 +56,14 @@ impl FileCollector {
         }
     }
     
+    /// Check whether a file has one of the valid extensions
+    pub fn accepts(&self, path: impl AsRef<Path>) -> bool {
+        let extensions: Vec<&str> = self.valid_extensions.iter()
+            .map(|s| s.as_str())
+            .collect();
+        file_utils::has_any_extension(path, &extensions)
+    }
+    
     /// Collect all files with valid extensions from the input directory
     pub fn collect_files(&self, input_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
         let input_dir = input_dir.as_ref();
@@ -43,6 +81,80 @@ impl FileCollector {
         Ok(files)
     }
     
+    /// Collect files like `collect_files`, also reporting every path that
+    /// couldn't be read instead of silently skipping it
+    pub fn collect_files_with_errors(&self, input_dir: impl AsRef<Path>) -> Result<FileCollection> {
+        let input_dir = input_dir.as_ref();
+        debug!("Collecting files from directory: {}", input_dir.display());
+        
+        let extensions: Vec<&str> = self.valid_extensions.iter()
+            .map(|s| s.as_str())
+            .collect();
+        
+        let (files, collection_errors) = file_utils::get_files_with_extensions_and_errors(input_dir, &extensions)?;
+        
+        debug!("Collected {} files for processing, {} paths unreadable", files.len(), collection_errors.len());
+        Ok(FileCollection { files, collection_errors })
+    }
+    
+    /// Collect files like `collect_files`, walking the directory tree in parallel.
+    /// Useful for deep trees on fast storage; the result is sorted by path.
+    pub fn collect_files_parallel(&self, input_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let input_dir = input_dir.as_ref();
+        debug!("Collecting files in parallel from directory: {}", input_dir.display());
+        
+        let extensions: Vec<&str> = self.valid_extensions.iter()
+            .map(|s| s.as_str())
+            .collect();
+        
+        let files = file_utils::get_files_with_extensions_parallel(input_dir, &extensions)?;
+        
+        debug!("Collected {} files for processing", files.len());
+        Ok(files)
+    }
+    
+    /// Read every file with a valid extension from a zip archive, in archive
+    /// order. Pass each file's content to the parser with its `path`.
+    #[cfg(feature = "zip")]
+    pub fn collect_from_zip(&self, path: impl AsRef<Path>) -> Result<Vec<ArchiveFile>> {
+        use std::io::Read;
+        
+        let path = path.as_ref();
+        debug!("Collecting files from archive: {}", path.display());
+        
+        let archive_file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open archive {}", path.display()))?;
+        let mut archive = zip::ZipArchive::new(archive_file)
+            .with_context(|| format!("Failed to read zip archive {}", path.display()))?;
+        
+        let mut files = Vec::new();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)
+                .with_context(|| format!("Failed to read entry {} of {}", i, path.display()))?;
+            
+            // Entries with unsafe names such as `../x.hpp` are skipped
+            let Some(name) = entry.enclosed_name() else {
+                continue;
+            };
+            if !entry.is_file() || !self.accepts(&name) {
+                continue;
+            }
+            
+            let mut content = String::new();
+            entry.read_to_string(&mut content)
+                .with_context(|| format!("Failed to read {} from {}", name.display(), path.display()))?;
+            if content.starts_with(file_utils::UTF8_BOM) {
+                content.remove(0);
+            }
+            
+            trace!("Found file in archive: {}", name.display());
+            files.push(ArchiveFile { path: path.join(name), content });
+        }
+        
+        debug!("Collected {} files from archive {}", files.len(), path.display());
+        Ok(files)
+    }
+    
     /// Add a valid file extension
     pub fn add_extension(&mut self, extension: &str) {
         if !self.valid_extensions.contains(&extension.to_string()) {
@@ -1,6 +1,8 @@
+mod extractor;
 mod file_collector;
 mod parser;
 mod progress;
+mod preprocessor;
 pub mod simple_parser;
 
 use std::path::{Path, PathBuf};
@@ -13,10 +15,14 @@ use rayon::prelude::*;
 use crate::class::types::{ClassScanOptions, ScanErrors};
 
 // Re-export from submodules
-pub use file_collector::FileCollector;
+pub use extractor::ClassExtractor;
+pub use file_collector::{FileCollection, FileCollector};
+#[cfg(feature = "zip")]
+pub use file_collector::ArchiveFile;
 pub use parser::ClassParser;
-pub use progress::ProgressTracker;
-pub use simple_parser::{SimpleParser, ClassBlock, Block};
+pub use progress::{ProgressTracker, ThroughputTracker};
+pub use preprocessor::{MacroDefinition, MacroTable, strip_inactive_branches};
+pub use simple_parser::{SimpleParser, ClassBlock, Block, EnumBlock, parse_class_line};
 
 /// Class scanner for finding and parsing class files
 #[derive(Debug)]
@@ -80,6 +86,10 @@ impl ClassScanner {
     
     /// Scan files in parallel and return the results
     pub fn scan_files_parallel(&mut self, files: &[PathBuf]) -> Result<Vec<(PathBuf, Vec<Block>)>> {
+        // Error logs are written under the output directory
+        crate::utils::file_utils::ensure_dir_exists(&self.output_dir)
+            .with_context(|| format!("Output directory {} is not usable", self.output_dir.display()))?;
+        
         // Create a thread pool for parallel processing
         let pool = rayon::ThreadPoolBuilder::new()
             .num_threads(self.options.parallel_threads.unwrap_or_else(num_cpus::get))
@@ -28,7 +28,7 @@ impl ClassParser {
         Self {
             options: options.clone(),
             output_dir: output_dir.as_ref().to_path_buf(),
-            simple_parser: SimpleParser::new(options.verbose_errors),
+            simple_parser: SimpleParser::from_options(&options),
         }
     }
     
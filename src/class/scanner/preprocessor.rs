@@ -0,0 +1,269 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use regex::Regex;
+use lazy_static::lazy_static;
+
+/// Maximum nesting depth when expanding macros that expand to other macros
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+/// A `#define` directive found in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroDefinition {
+    /// Name of the macro
+    pub name: String,
+    
+    /// Parameter names for function-like macros
+    pub params: Option<Vec<String>>,
+    
+    /// Replacement text
+    pub body: String,
+    
+    /// Byte offset of the directive in the file
+    pub position: usize,
+}
+
+/// The macros defined in a file, in source order
+#[derive(Debug, Clone, Default)]
+pub struct MacroTable {
+    /// Definitions in the order they appear
+    definitions: Vec<MacroDefinition>,
+    
+    /// Names removed by `#undef`, with the byte offset of the directive
+    undefinitions: Vec<(String, usize)>,
+}
+
+impl MacroTable {
+    /// Collect the `#define` and `#undef` directives from content
+    pub fn from_content(content: &str) -> Self {
+        lazy_static! {
+            static ref DEFINE_RE: Regex = Regex::new(
+                r"(?m)^[ \t]*#define[ \t]+([A-Za-z_][A-Za-z0-9_]*)(\([^)]*\))?[ \t]*((?:[^\n]*\\\r?\n)*[^\n]*)"
+            ).unwrap();
+            static ref UNDEF_RE: Regex = Regex::new(
+                r"(?m)^[ \t]*#undef[ \t]+([A-Za-z_][A-Za-z0-9_]*)"
+            ).unwrap();
+        }
+        
+        let definitions = DEFINE_RE.captures_iter(content)
+            .map(|cap| MacroDefinition {
+                name: cap[1].to_string(),
+                params: cap.get(2).map(|m| {
+                    m.as_str().trim_matches(|c| c == '(' || c == ')')
+                        .split(',')
+                        .map(|p| p.trim().to_string())
+                        .filter(|p| !p.is_empty())
+                        .collect()
+                }),
+                body: cap[3].replace("\\\r\n", " ").replace("\\\n", " ").trim().to_string(),
+                position: cap.get(0).unwrap().start(),
+            })
+            .collect();
+        
+        let undefinitions = UNDEF_RE.captures_iter(content)
+            .map(|cap| (cap[1].to_string(), cap.get(0).unwrap().start()))
+            .collect();
+        
+        Self { definitions, undefinitions }
+    }
+    
+    /// All collected definitions in source order
+    pub fn definitions(&self) -> &[MacroDefinition] {
+        &self.definitions
+    }
+    
+    /// Look up the definition of `name` in effect at byte offset `position`,
+    /// or `None` if it was never defined or was `#undef`ined since
+    pub fn active_at(&self, name: &str, position: usize) -> Option<&MacroDefinition> {
+        let def = self.definitions.iter()
+            .filter(|def| def.position < position)
+            .rfind(|def| def.name == name)?;
+        
+        let undefined = self.undefinitions.iter()
+            .any(|(undef, at)| undef == name && def.position < *at && *at < position);
+        (!undefined).then_some(def)
+    }
+    
+    /// Expand a macro invocation such as `NAME` or `NAME(args)` using the
+    /// definitions in effect at `position`. Text that isn't a known macro is
+    /// returned unchanged.
+    pub fn expand(&self, expr: &str, position: usize) -> String {
+        let mut current = expr.trim().to_string();
+        
+        for _ in 0..MAX_EXPANSION_DEPTH {
+            match self.expand_once(&current, position) {
+                Some(expanded) if expanded != current => current = expanded,
+                _ => break,
+            }
+        }
+        
+        current
+    }
+    
+    /// Expand the outermost macro invocation in `expr`, if any
+    fn expand_once(&self, expr: &str, position: usize) -> Option<String> {
+        let (name, args) = match expr.find('(') {
+            Some(open) if expr.ends_with(')') => {
+                let args = split_macro_args(&expr[open + 1..expr.len() - 1]);
+                (expr[..open].trim(), Some(args))
+            }
+            _ => (expr, None),
+        };
+        
+        let def = self.active_at(name, position)?;
+        let body = match (&def.params, args) {
+            (Some(params), Some(args)) => substitute_params(&def.body, params, &args),
+            (None, None) => def.body.clone(),
+            _ => return None,
+        };
+        
+        Some(body.trim().to_string())
+    }
+}
+
+/// Split macro arguments on top-level commas
+fn split_macro_args(args: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    
+    for c in args.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                result.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    
+    if !current.trim().is_empty() || !result.is_empty() {
+        result.push(current.trim().to_string());
+    }
+    result
+}
+
+/// Replace parameter names in a macro body, handling `#param` and `##` pasting
+fn substitute_params(body: &str, params: &[String], args: &[String]) -> String {
+    lazy_static! {
+        static ref TOKEN_RE: Regex = Regex::new(r"##|#?[A-Za-z_][A-Za-z0-9_]*").unwrap();
+        static ref PASTE_RE: Regex = Regex::new(r"\s*##\s*").unwrap();
+    }
+    
+    let arg_for = |name: &str| {
+        params.iter().position(|p| p == name)
+            .map(|i| args.get(i).cloned().unwrap_or_default())
+    };
+    
+    let substituted = TOKEN_RE.replace_all(body, |caps: &regex::Captures| {
+        let token = &caps[0];
+        if token == "##" {
+            return token.to_string();
+        }
+        if let Some(name) = token.strip_prefix('#') {
+            return match arg_for(name) {
+                Some(arg) => format!("\"{}\"", arg),
+                None => token.to_string(),
+            };
+        }
+        arg_for(token).unwrap_or_else(|| token.to_string())
+    });
+    
+    PASTE_RE.replace_all(&substituted, "").into_owned()
+}
+
+/// State of one `#ifdef`/`#ifndef`/`#if` block while evaluating conditionals
+struct ConditionalFrame {
+    /// Whether the block itself sits in active code
+    parent_active: bool,
+    
+    /// Whether an earlier branch of the block was taken
+    taken: bool,
+    
+    /// Whether the current branch is active
+    active: bool,
+}
+
+/// Blank out code in inactive `#ifdef`/`#ifndef` branches, keeping line
+/// breaks and byte offsets intact.
+///
+/// A symbol counts as defined if it is in `defined` or was `#define`d earlier
+/// in active code, and hasn't been `#undef`ined since. `#if` and `#elif`
+/// expressions aren't evaluated and are treated as true, so their first
+/// branch is kept.
+pub fn strip_inactive_branches<'a>(content: &'a str, defined: &HashSet<String>) -> Cow<'a, str> {
+    if !content.contains("#if") {
+        return Cow::Borrowed(content);
+    }
+    
+    let mut local_defines: HashSet<&str> = HashSet::new();
+    let mut local_undefines: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<ConditionalFrame> = Vec::new();
+    let mut output = String::with_capacity(content.len());
+    
+    for line in content.split_inclusive('\n') {
+        let active = stack.last().is_none_or(|frame| frame.active);
+        
+        let directive = line.trim_start().strip_prefix('#').map(|rest| {
+            let rest = rest.trim_start();
+            let keyword_end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+            let argument = rest[keyword_end..].split_whitespace().next().unwrap_or("");
+            (&rest[..keyword_end], argument)
+        });
+        
+        match directive {
+            Some((keyword @ ("ifdef" | "ifndef" | "if"), symbol)) => {
+                let condition = match keyword {
+                    "if" => true,
+                    _ => {
+                        let is_defined = !local_undefines.contains(symbol)
+                            && (defined.contains(symbol) || local_defines.contains(symbol));
+                        is_defined == (keyword == "ifdef")
+                    }
+                };
+                stack.push(ConditionalFrame {
+                    parent_active: active,
+                    taken: condition,
+                    active: active && condition,
+                });
+            }
+            Some(("elif", _)) | Some(("else", _)) => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.active = frame.parent_active && !frame.taken;
+                    frame.taken = true;
+                }
+            }
+            Some(("endif", _)) => {
+                stack.pop();
+            }
+            Some(("define", symbol)) if active => {
+                let name_end = symbol.find('(').unwrap_or(symbol.len());
+                let name = &symbol[..name_end];
+                local_undefines.remove(name);
+                local_defines.insert(name);
+            }
+            Some(("undef", symbol)) if active => {
+                local_defines.remove(symbol);
+                local_undefines.insert(symbol);
+            }
+            _ => {}
+        }
+        
+        if active {
+            output.push_str(line);
+        } else {
+            for c in line.chars() {
+                match c {
+                    '\n' | '\r' => output.push(c),
+                    _ => output.extend(std::iter::repeat_n(' ', c.len_utf8())),
+                }
+            }
+        }
+    }
+    
+    Cow::Owned(output)
+}
@@ -1,10 +1,91 @@
+use std::collections::VecDeque;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use indicatif::{ProgressBar, ProgressStyle, MultiProgress};
 use rayon::prelude::*;
 
+/// Number of recent completions used for the rolling throughput
+const THROUGHPUT_WINDOW: usize = 50;
+
+/// Rolling throughput estimate over the most recent completed items.
+///
+/// Each sample is the time between one completion and the next, so the
+/// estimate reflects the combined rate of all worker threads.
+#[derive(Debug, Clone)]
+pub struct ThroughputTracker {
+    /// Maximum number of intervals kept
+    window: usize,
+    
+    /// Most recent completion intervals, oldest first
+    intervals: VecDeque<Duration>,
+    
+    /// Sum of `intervals`
+    total: Duration,
+}
+
+impl ThroughputTracker {
+    /// Create a tracker averaging over the last `window` intervals
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            intervals: VecDeque::new(),
+            total: Duration::ZERO,
+        }
+    }
+    
+    /// Record the time elapsed since the previous item completed
+    pub fn record(&mut self, interval: Duration) {
+        self.intervals.push_back(interval);
+        self.total += interval;
+        if self.intervals.len() > self.window
+            && let Some(oldest) = self.intervals.pop_front() {
+            self.total -= oldest;
+        }
+    }
+    
+    /// Items completed per second over the window, if anything has been timed
+    pub fn items_per_second(&self) -> Option<f64> {
+        let seconds = self.total.as_secs_f64();
+        (seconds > 0.0).then(|| self.intervals.len() as f64 / seconds)
+    }
+    
+    /// Estimated time to complete `remaining` more items at the current rate
+    pub fn eta(&self, remaining: usize) -> Option<Duration> {
+        self.items_per_second()
+            .map(|rate| Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+/// Throughput shared between the worker threads of one tracked run
+struct SharedThroughput {
+    tracker: ThroughputTracker,
+    last_completion: Instant,
+}
+
+impl SharedThroughput {
+    fn new() -> Mutex<Self> {
+        Mutex::new(Self {
+            tracker: ThroughputTracker::new(THROUGHPUT_WINDOW),
+            last_completion: Instant::now(),
+        })
+    }
+    
+    /// Record a completion and describe the current rate for the progress message
+    fn complete(&mut self, remaining: usize) -> String {
+        let now = Instant::now();
+        self.tracker.record(now - self.last_completion);
+        self.last_completion = now;
+        
+        match (self.tracker.items_per_second(), self.tracker.eta(remaining)) {
+            (Some(rate), Some(eta)) => format!("{:.1}/s, ETA {}s", rate, eta.as_secs()),
+            _ => String::new(),
+        }
+    }
+}
+
 /// Progress tracker for displaying progress during scanning
 #[derive(Debug, Default)]
 pub struct ProgressTracker {}
@@ -27,7 +108,7 @@ impl ProgressTracker {
         let progress_bar = if items.len() > 10 {
             let pb = multi_progress.add(ProgressBar::new(items.len() as u64));
             pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} items ({eta})")
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} items {msg}")
                 .unwrap()
                 .progress_chars("#>-"));
             Some(Arc::new(pb))
@@ -37,24 +118,27 @@ impl ProgressTracker {
         
         // Create an atomic counter for tracking progress
         let processed_count = Arc::new(AtomicUsize::new(0));
+        let throughput = SharedThroughput::new();
         
         // Process items in parallel
         let results: Vec<_> = items.par_iter()
             .filter_map(|item| {
+                // Perform the operation
+                let result = operation(item);
+                
                 // Update progress
                 let current_count = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+                let rate = throughput.lock().unwrap().complete(items.len() - current_count);
                 if let Some(pb) = &progress_bar {
                     pb.set_position(current_count as u64);
                     
                     // Update message occasionally to avoid too many updates
-                    if current_count % 10 == 0 || current_count == 1 || current_count == items.len() {
-                        // Simple progress message
-                        pb.set_message(format!("Processing item {}/{}", current_count, items.len()));
+                    if current_count.is_multiple_of(10) || current_count == 1 || current_count == items.len() {
+                        pb.set_message(rate);
                     }
                 }
                 
-                // Perform the operation
-                operation(item)
+                result
             })
             .collect();
         
@@ -77,7 +161,7 @@ impl ProgressTracker {
         let progress_bar = if paths.len() > 10 {
             let pb = multi_progress.add(ProgressBar::new(paths.len() as u64));
             pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} files ({eta})")
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} files {msg}")
                 .unwrap()
                 .progress_chars("#>-"));
             Some(Arc::new(pb))
@@ -87,25 +171,28 @@ impl ProgressTracker {
         
         // Create an atomic counter for tracking progress
         let processed_count = Arc::new(AtomicUsize::new(0));
+        let throughput = SharedThroughput::new();
         
         // Process items in parallel
         let results: Vec<_> = paths.par_iter()
             .filter_map(|path| {
+                // Perform the operation
+                let result = operation(path);
+                
                 // Update progress
                 let current_count = processed_count.fetch_add(1, Ordering::SeqCst) + 1;
+                let rate = throughput.lock().unwrap().complete(paths.len() - current_count);
                 if let Some(pb) = &progress_bar {
                     pb.set_position(current_count as u64);
                     
                     // Update message occasionally to avoid too many updates
-                    if current_count % 10 == 0 || current_count == 1 || current_count == paths.len() {
-                        if let Some(file_name) = path.file_name() {
-                            pb.set_message(format!("Processing: {}", file_name.to_string_lossy()));
-                        }
+                    if (current_count.is_multiple_of(10) || current_count == 1 || current_count == paths.len())
+                        && let Some(file_name) = path.file_name() {
+                        pb.set_message(format!("{} ({})", file_name.to_string_lossy(), rate));
                     }
                 }
                 
-                // Perform the operation
-                operation(path)
+                result
             })
             .collect();
         
@@ -1,13 +1,42 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use anyhow::{Result, Context};
 use log::{debug, warn};
 use regex::Regex;
 use lazy_static::lazy_static;
 
+use crate::class::types::{ClassScanOptions, ScanMode};
 use crate::utils::file_utils;
+use super::preprocessor::{self, MacroTable};
+
+lazy_static! {
+    // Match class definitions with optional inheritance
+    // Handles both "class Name;" and "class Name: Parent {", where the
+    // parent may be a macro call such as "BASECLASS(rifle)" or quoted
+    // as in generated files; the quotes are not captured
+    static ref CLASS_RE: Regex = Regex::new(
+        r#"class\s+([A-Za-z0-9_]+)(?:\s*:\s*"?([A-Za-z0-9_]+(?:\([^()]*\))?)"?)?[\s{;]"#
+    ).unwrap();
+}
+
+/// Parse the name and parent from a single class declaration line such as
+/// `class A;` or `class A: B {`, using the same pattern as the parser.
+///
+/// The line must start with the declaration, ignoring leading whitespace.
+/// Parent macros aren't expanded, since there is no file to define them in.
+pub fn parse_class_line(line: &str) -> Option<(String, Option<String>)> {
+    // The pattern needs a terminator after the name, which a bare line may lack
+    let line = format!("{}\n", line.trim());
+    let cap = CLASS_RE.captures(&line)?;
+    if cap.get(0)?.start() != 0 {
+        return None;
+    }
+    Some((cap[1].to_string(), cap.get(2).map(|m| m.as_str().to_string())))
+}
 
 /// A simplified class block representing a class found in a file
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ClassBlock {
     /// Name of the class
     pub name: String,
@@ -15,10 +44,59 @@ pub struct ClassBlock {
     /// Parent class name, if any
     pub parent: Option<String>,
     
+    /// Properties assigned directly in the class body
+    pub properties: Vec<(String, String)>,
+    
+    /// Names of properties whose values were string literals, with their
+    /// quotes stripped in `properties`
+    pub quoted_properties: Vec<String>,
+    
+    /// Whether the class has a body, even an empty `{}` one, as opposed to
+    /// being a `class Name;` forward declaration
+    pub is_definition: bool,
+    
+    /// Names of the enclosing classes, outermost first
+    pub container_path: Vec<String>,
+    
+    /// 1-based line of the class declaration
+    pub line: usize,
+    
+    /// 1-based column, in characters, where the declaration starts on its line
+    pub column: usize,
+    
+    /// `@key value` tags from comments directly above the class, when
+    /// annotation capture is enabled
+    pub annotations: Vec<(String, String)>,
+    
+    /// Byte range of the `class` keyword, when span capture is enabled
+    pub keyword_span: Option<(usize, usize)>,
+    
+    /// Byte range of the class name, when span capture is enabled
+    pub name_span: Option<(usize, usize)>,
+    
+    /// The exact text matched by the class pattern, including the trailing
+    /// `{`, `;` or whitespace, when raw match capture is enabled
+    pub raw_match: Option<String>,
+    
+    /// Text between the declaration and its `{` or `;` on the same line, such
+    /// as `access = 3` in `class X: Y access = 3 {`, when metadata capture is
+    /// enabled
+    pub declaration_metadata: Option<String>,
+    
     /// Path to the file where this class was found
     pub file_path: PathBuf,
 }
 
+/// An `enum { ... }` block found in a file
+#[derive(Debug, Clone)]
+pub struct EnumBlock {
+    /// Members of the enum with their explicit values, if any
+    pub members: Vec<(String, Option<String>)>,
+    
+    /// Byte range of the block, from the `enum` keyword to the closing brace
+    pub span: (usize, usize),
+}
+
 /// A compatibility type to match cpp_parser::Block for easier migration
 #[derive(Debug, Clone)]
 pub struct Block {
@@ -36,16 +114,80 @@ pub struct Block {
 }
 
 /// A simple parser that extracts class definitions from C++ files using regex
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SimpleParser {
     /// Whether to output verbose logs
     pub verbose: bool,
+    
+    /// Whether to record byte spans of each class keyword and name.
+    /// Offsets refer to the content passed to `parse_content`.
+    pub capture_spans: bool,
+    
+    /// Whether to record the text matched for each class, for debugging patterns
+    pub capture_raw_matches: bool,
+    
+    /// Whether to capture trailing declaration metadata. A body following
+    /// the metadata is then recognized instead of the class being read as a
+    /// forward declaration.
+    pub capture_declaration_metadata: bool,
+    
+    /// Whether to capture `// @key value` tags from the comment lines
+    /// directly above each class
+    pub capture_annotations: bool,
+    
+    /// Whether to extract the properties of class bodies
+    pub capture_properties: bool,
+    
+    /// Symbols treated as defined when evaluating `#ifdef`/`#ifndef`
+    pub defined_symbols: HashSet<String>,
+    
+    /// Custom class-matching pattern overriding the default one
+    class_pattern: Option<Regex>,
 }
 
 impl SimpleParser {
     /// Create a new simple parser
     pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+        Self {
+            verbose,
+            capture_spans: false,
+            capture_raw_matches: false,
+            capture_declaration_metadata: false,
+            capture_annotations: false,
+            capture_properties: true,
+            defined_symbols: HashSet::new(),
+            class_pattern: None,
+        }
+    }
+    
+    /// Create a simple parser configured from scan options
+    pub fn from_options(options: &ClassScanOptions) -> Self {
+        Self {
+            capture_spans: options.capture_spans,
+            capture_raw_matches: options.capture_raw_matches,
+            capture_declaration_metadata: options.capture_declaration_metadata,
+            capture_annotations: options.capture_annotations,
+            capture_properties: options.scan_mode != ScanMode::StructureOnly,
+            defined_symbols: options.defined_symbols.clone(),
+            ..Self::new(options.verbose_errors)
+        }
+    }
+    
+    /// Use a custom regex to match class declarations.
+    ///
+    /// Capture group 1 must be the class name and the optional group 2 the
+    /// parent name. The match should end on the character following the
+    /// declaration (`{`, `;` or whitespace), like the default pattern does.
+    pub fn with_pattern(mut self, custom_regex: &str) -> Result<Self> {
+        let pattern = Regex::new(custom_regex)
+            .with_context(|| format!("Invalid class pattern: {}", custom_regex))?;
+        
+        if pattern.captures_len() < 2 {
+            anyhow::bail!("Class pattern must capture the class name in group 1: {}", custom_regex);
+        }
+        
+        self.class_pattern = Some(pattern);
+        Ok(self)
     }
     
     /// Parse a file and extract class definitions
@@ -61,19 +203,94 @@ impl SimpleParser {
     
     /// Parse content and extract class definitions
     pub fn parse_content(&self, content: String, file_path: &Path) -> Result<Vec<ClassBlock>> {
-        lazy_static! {
-            // Match class definitions with optional inheritance
-            // Handles both "class Name;" and "class Name: Parent {"
-            static ref CLASS_RE: Regex = Regex::new(
-                r"class\s+([A-Za-z0-9_]+)(?:\s*:\s*([A-Za-z0-9_]+))?[\s{;]"
-            ).unwrap();
-        }
+        // Code in inactive conditional branches is blanked, keeping offsets intact
+        let stripped = match preprocessor::strip_inactive_branches(&content, &self.defined_symbols) {
+            Cow::Owned(stripped) => Some(stripped),
+            Cow::Borrowed(_) => None,
+        };
+        let content = stripped.unwrap_or(content);
         
         let mut classes = Vec::new();
+        let macros = MacroTable::from_content(&content);
+        
+        // Enum bodies are not class bodies; anything matched inside them is ignored
+        let enum_spans: Vec<(usize, usize)> = find_enum_blocks(&content).iter()
+            .map(|block| block.span)
+            .collect();
         
-        for cap in CLASS_RE.captures_iter(&content) {
-            let class_name = cap[1].to_string();
-            let parent_name = cap.get(2).map(|m| m.as_str().to_string());
+        // Classes whose bodies are still open, with the position of their closing brace
+        let mut open_classes: Vec<(String, usize)> = Vec::new();
+        
+        // Line counting resumes from the previous match, since matches come in order
+        let mut line = 1;
+        let mut line_start = 0;
+        let mut line_counted_to = 0;
+        
+        let class_re = self.class_pattern.as_ref().unwrap_or(&CLASS_RE);
+        for cap in class_re.captures_iter(&content) {
+            let whole = cap.get(0).unwrap();
+            let skipped = &content[line_counted_to..whole.start()];
+            line += skipped.matches('\n').count();
+            if let Some(newline) = skipped.rfind('\n') {
+                line_start = line_counted_to + newline + 1;
+            }
+            line_counted_to = whole.start();
+            let column = content[line_start..whole.start()].chars().count() + 1;
+            if enum_spans.iter().any(|(start, end)| whole.start() > *start && whole.start() < *end) {
+                continue;
+            }
+            
+            let Some(name_match) = cap.get(1) else {
+                continue;
+            };
+            let class_name = name_match.as_str().to_string();
+            
+            let (keyword_span, name_span) = if self.capture_spans {
+                let keyword_span = content[whole.start()..].starts_with("class")
+                    .then(|| (whole.start(), whole.start() + "class".len()));
+                (keyword_span, Some((name_match.start(), name_match.end())))
+            } else {
+                (None, None)
+            };
+            let parent_name = cap.get(2).map(|m| macros.expand(m.as_str(), whole.start()));
+            
+            // Drop containers whose bodies closed before this class
+            while open_classes.last().is_some_and(|(_, close)| *close < whole.start()) {
+                open_classes.pop();
+            }
+            let container_path = open_classes.iter().map(|(name, _)| name.clone()).collect();
+            
+            // Metadata sits between the declaration and its terminator, so the
+            // body, if any, opens right after it
+            let declaration_metadata = if self.capture_declaration_metadata {
+                find_declaration_metadata(&content, whole.end() - 1)
+            } else {
+                None
+            };
+            
+            // Capture the properties of the class body, if it has one
+            let body_start = match &declaration_metadata {
+                Some((_, terminator)) => content[*terminator..].starts_with('{').then_some(*terminator),
+                None => find_body_start(&content, whole.end() - 1),
+            };
+            let (properties, quoted_properties) = match body_start {
+                Some(open) => {
+                    let close = find_matching_brace(&content, open).unwrap_or(content.len());
+                    open_classes.push((class_name.clone(), close));
+                    if self.capture_properties {
+                        extract_properties(&content[open + 1..close])
+                    } else {
+                        (Vec::new(), Vec::new())
+                    }
+                }
+                None => (Vec::new(), Vec::new()),
+            };
+            
+            let annotations = if self.capture_annotations {
+                preceding_annotations(&content, whole.start())
+            } else {
+                Vec::new()
+            };
             
             if self.verbose {
                 debug!("Found class: {} with parent: {:?} in {}", 
@@ -83,6 +300,17 @@ impl SimpleParser {
             classes.push(ClassBlock {
                 name: class_name,
                 parent: parent_name,
+                properties,
+                quoted_properties,
+                is_definition: body_start.is_some(),
+                container_path,
+                line,
+                column,
+                annotations,
+                keyword_span,
+                name_span,
+                raw_match: self.capture_raw_matches.then(|| whole.as_str().to_string()),
+                declaration_metadata: declaration_metadata.map(|(metadata, _)| metadata),
                 file_path: file_path.to_path_buf(),
             });
         }
@@ -91,6 +319,19 @@ impl SimpleParser {
         Ok(classes)
     }
     
+    /// Extract the `#define NAME value` constants found in active code, in source order
+    pub fn parse_defines(&self, content: &str) -> Vec<(String, String)> {
+        let content = preprocessor::strip_inactive_branches(content, &self.defined_symbols);
+        MacroTable::from_content(&content).definitions().iter()
+            .map(|def| (def.name.clone(), def.body.clone()))
+            .collect()
+    }
+    
+    /// Extract the `enum { ... }` blocks found in content
+    pub fn parse_enums(&self, content: &str) -> Vec<EnumBlock> {
+        find_enum_blocks(content)
+    }
+    
     /// Convert our ClassBlock to the compatibility Block type
     pub fn to_blocks(&self, class_blocks: Vec<ClassBlock>) -> Vec<Block> {
         class_blocks.into_iter()
@@ -102,4 +343,383 @@ impl SimpleParser {
             })
             .collect()
     }
-} 
\ No newline at end of file
+}
+
+/// Find every `enum { ... }` block and record its members
+fn find_enum_blocks(content: &str) -> Vec<EnumBlock> {
+    lazy_static! {
+        static ref ENUM_RE: Regex = Regex::new(r"\benum\s*\{").unwrap();
+    }
+    
+    let mut blocks = Vec::new();
+    for m in ENUM_RE.find_iter(content) {
+        let open = m.end() - 1;
+        let close = find_matching_brace(content, open).unwrap_or(content.len());
+        
+        let members = split_array_elements(&strip_comments(&content[open + 1..close])).iter()
+            .filter_map(|member| {
+                let member = member.trim();
+                if member.is_empty() {
+                    return None;
+                }
+                Some(match member.split_once('=') {
+                    Some((name, value)) => (name.trim().to_string(), Some(value.trim().to_string())),
+                    None => (member.to_string(), None),
+                })
+            })
+            .collect();
+        
+        blocks.push(EnumBlock {
+            members,
+            span: (m.start(), close),
+        });
+    }
+    
+    blocks
+}
+
+/// Collect `@key value` tags from the `//` comment lines directly above the
+/// line containing `class_start`. Stops at the first line that isn't a comment.
+fn preceding_annotations(content: &str, class_start: usize) -> Vec<(String, String)> {
+    let line_start = content[..class_start].rfind('\n').map_or(0, |i| i + 1);
+    if !content[line_start..class_start].trim().is_empty() {
+        return Vec::new();
+    }
+    
+    let mut annotations = Vec::new();
+    for line in content[..line_start].lines().rev() {
+        let Some(comment) = line.trim().strip_prefix("//") else {
+            break;
+        };
+        if let Some(tag) = comment.trim().strip_prefix('@') {
+            let (key, value) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            if !key.is_empty() {
+                annotations.push((key.to_string(), value.trim().to_string()));
+            }
+        }
+    }
+    
+    // Collected bottom-up; report them in source order
+    annotations.reverse();
+    annotations
+}
+
+/// Find the opening brace of a class body, starting at the end of a class match
+fn find_body_start(content: &str, from: usize) -> Option<usize> {
+    content[from..].char_indices()
+        .find(|(_, c)| !c.is_whitespace())
+        .filter(|(_, c)| *c == '{')
+        .map(|(i, _)| from + i)
+}
+
+/// Find non-empty text between a declaration ending at `from` and a `{` or
+/// `;` on the same line, returning it trimmed with the terminator's position
+fn find_declaration_metadata(content: &str, from: usize) -> Option<(String, usize)> {
+    let rest = &content[from..];
+    let end = rest.find(['{', ';', '\n'])?;
+    if rest[end..].starts_with('\n') {
+        return None;
+    }
+    let metadata = rest[..end].trim();
+    (!metadata.is_empty()).then(|| (metadata.to_string(), from + end))
+}
+
+/// Find the brace matching the one at `open`, skipping strings and comments
+fn find_matching_brace(content: &str, open: usize) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut i = open;
+    
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => in_string = !in_string,
+            b'/' if !in_string && bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if !in_string && bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'{' if !in_string => depth += 1,
+            b'}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    
+    None
+}
+
+/// Split a class body into its top-level statements, dropping comments and
+/// preprocessor lines. Nested class bodies stay inside their statement.
+fn split_statements(body: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut chars = body.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        if in_string {
+            current.push(c);
+            if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        
+        match c {
+            '"' => {
+                in_string = true;
+                current.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        current.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '#' if current.trim().is_empty() => {
+                // Skip preprocessor directives, honouring line continuations
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' && prev != '\\' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+            }
+            ';' if depth == 0 => {
+                statements.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    
+    if !current.trim().is_empty() {
+        statements.push(current);
+    }
+    
+    statements
+}
+
+/// Extract the `name = value` assignments from a class body, along with the
+/// names of those whose value was a quoted string
+fn extract_properties(body: &str) -> (Vec<(String, String)>, Vec<String>) {
+    let mut properties = Vec::new();
+    let mut quoted = Vec::new();
+    
+    for statement in split_statements(body) {
+        let statement = statement.trim();
+        if matches!(statement.split_whitespace().next(), Some("class" | "delete")) {
+            continue;
+        }
+        
+        let Some((name, value)) = statement.split_once('=') else {
+            continue;
+        };
+        
+        let name = name.trim().trim_end_matches('+').trim_end().trim_end_matches("[]").trim_end();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        
+        let value = value.trim();
+        let value = if value.starts_with('{') {
+            normalize_array_value(value)
+        } else if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            quoted.push(name.to_string());
+            value[1..value.len() - 1].to_string()
+        } else {
+            value.to_string()
+        };
+        
+        properties.push((name.to_string(), value));
+    }
+    
+    (properties, quoted)
+}
+
+/// Normalize an array value into a canonical `{a, b, c}` form.
+///
+/// Each element is trimmed and runs of whitespace outside string literals are
+/// collapsed, so arrays that only differ in author spacing compare equal.
+fn normalize_array_value(value: &str) -> String {
+    let value = value.trim();
+    
+    // Stop at the brace closing the array, so stray content after it is dropped
+    let value = match matching_brace_end(value) {
+        Some(end) => &value[..end],
+        None => value,
+    };
+    let Some(inner) = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) else {
+        return collapse_whitespace(value);
+    };
+    
+    let mut elements = split_array_elements(inner);
+    
+    // Tolerate a trailing comma after the last element
+    if elements.len() > 1 && elements.last().is_some_and(|last| last.trim().is_empty()) {
+        elements.pop();
+    }
+    
+    let elements: Vec<String> = elements.iter()
+        .map(|element| {
+            let element = element.trim();
+            if element.starts_with('{') {
+                normalize_array_value(element)
+            } else {
+                collapse_whitespace(element)
+            }
+        })
+        .collect();
+    
+    if elements.len() == 1 && elements[0].is_empty() {
+        return "{}".to_string();
+    }
+    
+    format!("{{{}}}", elements.join(", "))
+}
+
+/// Find the byte offset just past the `}` matching the `{` that starts
+/// `value`, skipping braces inside string literals
+fn matching_brace_end(value: &str) -> Option<usize> {
+    if !value.starts_with('{') {
+        return None;
+    }
+    
+    let mut depth = 0usize;
+    let mut in_string = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    None
+}
+
+/// Remove line and block comments that are not inside string literals
+fn strip_comments(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut chars = content.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_string = !in_string,
+            '/' if !in_string && chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        result.push(c);
+                        break;
+                    }
+                }
+                continue;
+            }
+            '/' if !in_string && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        result.push(c);
+    }
+    
+    result
+}
+
+/// Split the inside of an array on its top-level commas
+fn split_array_elements(inner: &str) -> Vec<String> {
+    let mut elements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    
+    for c in inner.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => {
+                elements.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    
+    elements.push(current);
+    elements
+}
+
+/// Collapse runs of whitespace outside string literals into a single space
+fn collapse_whitespace(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut in_string = false;
+    let mut pending_space = false;
+    
+    for c in value.trim().chars() {
+        if !in_string && c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space {
+            result.push(' ');
+            pending_space = false;
+        }
+        if c == '"' {
+            in_string = !in_string;
+        }
+        result.push(c);
+    }
+    
+    result
+}
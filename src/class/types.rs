@@ -1,8 +1,17 @@
-use std::path::PathBuf;
-use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use chrono::{DateTime, Utc};
+use log::warn;
+
+use crate::utils::file_utils;
+use crate::utils::hash_utils::HashAlgorithm;
 
 /// Represents a processed class from a parsed file
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ProcessedClass {
     /// Name of the class
     pub name: String,
@@ -10,11 +19,99 @@ pub struct ProcessedClass {
     /// Parent class name, if any
     pub parent: Option<String>,
     
-    /// Properties of the class as key-value pairs
-    pub properties: Vec<(String, String)>,
+    /// Properties of the class as key-value pairs.
+    ///
+    /// Values are reference counted so repeated values can share one allocation
+    /// (see `DatabaseOperations::intern_strings`).
+    pub properties: Vec<(String, Arc<str>)>,
+    
+    /// Names of the enclosing classes, outermost first
+    #[serde(default)]
+    pub container_path: Vec<String>,
+    
+    /// Whether this is a `class Name;` forward declaration without a body
+    #[serde(default)]
+    pub is_forward_declaration: bool,
+    
+    /// Names of properties whose values were cut to `max_property_value_len`
+    #[serde(default)]
+    pub truncated_properties: Vec<String>,
     
-    /// Path to the file where this class was found
+    /// Names of properties whose values were string literals. Quotes are
+    /// stripped from the stored value, so this tells `"1"` apart from `1`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quoted_properties: Vec<String>,
+    
+    /// `@key value` tags from comments directly above the class
+    #[serde(default)]
+    pub annotations: Vec<(String, String)>,
+    
+    /// Path to the file where this class was found. Saved with `/`
+    /// separators so databases can be shared between platforms.
+    #[serde(default, serialize_with = "serialize_path_key", deserialize_with = "deserialize_path_key")]
     pub file_path: Option<PathBuf>,
+    
+    /// File defining the parent class, when it was found in the same scan
+    /// and `resolve_parents` is enabled
+    #[serde(default, serialize_with = "serialize_path_key", deserialize_with = "deserialize_path_key")]
+    pub resolved_parent: Option<PathBuf>,
+    
+    /// Kind of config the class was found in
+    #[serde(default)]
+    pub source_kind: SourceKind,
+}
+
+impl ProcessedClass {
+    /// A short stable ID for cross-referencing the class in other systems,
+    /// hashed from its name, enclosing classes and file. The same class gets
+    /// the same ID on every scan, while same-named classes elsewhere get
+    /// different ones.
+    pub fn id(&self) -> String {
+        let file = self.file_path.as_ref()
+            .map(file_utils::path_key)
+            .unwrap_or_default();
+        let key = format!("{}\0{}\0{}", self.container_path.join("/"), self.name, file);
+        HashAlgorithm::Xxh3.hash_string(&key)
+    }
+}
+
+/// Serialize a path with `/` separators, whatever the platform
+fn serialize_path_key<S: Serializer>(path: &Option<PathBuf>, serializer: S) -> Result<S::Ok, S::Error> {
+    path.as_ref().map(file_utils::path_key).serialize(serializer)
+}
+
+/// Deserialize a path, converting `\` separators written on Windows to `/`
+fn deserialize_path_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PathBuf>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.map(|path| PathBuf::from(file_utils::path_key(path))))
+}
+
+/// Kind of config file a class comes from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SourceKind {
+    /// Addon configs such as `config.cpp` and included headers
+    Addon,
+    
+    /// Mission files such as `mission.sqm` and `description.ext`
+    Mission,
+    
+    /// Anything that can't be classified from its file name
+    #[default]
+    Unknown,
+}
+
+impl SourceKind {
+    /// Infer the source kind from a file's name and extension
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let extension = path.as_ref().extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        
+        match extension.as_str() {
+            "sqm" | "ext" => SourceKind::Mission,
+            "cpp" | "hpp" | "h" | "inc" => SourceKind::Addon,
+            _ => SourceKind::Unknown,
+        }
+    }
 }
 
 /// Errors encountered during the scanning process
@@ -28,7 +125,7 @@ pub struct ScanErrors {
 }
 
 /// Statistics about the class scanning process
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ClassScanStats {
     /// Total number of files processed
     pub total_files: usize,
@@ -39,6 +136,9 @@ pub struct ClassScanStats {
     /// Number of empty files encountered
     pub empty_files: usize,
     
+    /// Paths to files that contained no classes
+    pub empty_file_paths: Vec<PathBuf>,
+    
     /// Number of files containing classes
     pub files_with_classes: usize,
     
@@ -53,6 +153,114 @@ pub struct ClassScanStats {
     
     /// Paths to files that timed out during parsing
     pub timeout_file_paths: Vec<PathBuf>,
+    
+    /// Per-extension breakdown, keyed by lowercase extension without the dot
+    pub by_extension: HashMap<String, ExtensionStats>,
+    
+    /// Number of classes dropped by `exclude_parents`
+    pub excluded_classes: usize,
+    
+    /// Number of classes dropped by `blacklist_patterns`
+    pub blacklisted: usize,
+    
+    /// Number of classes dropped for names shorter than `min_class_name_len`
+    pub filtered: usize,
+    
+    /// Number of identical duplicate classes dropped by `deduplicate_identical`
+    pub duplicate_classes: usize,
+    
+    /// Number of forward declarations merged into a definition or an earlier
+    /// declaration of the same class
+    pub merged_declarations: usize,
+}
+
+impl ClassScanStats {
+    /// Number of files that were parsed without errors or timeouts
+    pub fn successful_files(&self) -> usize {
+        self.total_files.saturating_sub(self.error_files + self.timeout_files)
+    }
+    
+    /// Fraction of processed files that parsed successfully, from 0.0 to 1.0.
+    /// A scan that processed no files counts as fully successful.
+    pub fn success_rate(&self) -> f64 {
+        if self.total_files == 0 {
+            return 1.0;
+        }
+        self.successful_files() as f64 / self.total_files as f64
+    }
+    
+    /// Average number of classes per successfully parsed file
+    pub fn avg_classes_per_file(&self) -> f64 {
+        match self.successful_files() {
+            0 => 0.0,
+            files => self.total_classes as f64 / files as f64,
+        }
+    }
+    
+    /// Set the per-extension class counts from the given classes, keeping the
+    /// file and error counts
+    pub fn recount_classes_by_extension(&mut self, classes: &[ProcessedClass]) {
+        for extension_stats in self.by_extension.values_mut() {
+            extension_stats.classes = 0;
+        }
+        for class in classes {
+            let extension = class.file_path.as_deref()
+                .and_then(|path| path.extension())
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            self.by_extension.entry(extension).or_default().classes += 1;
+        }
+    }
+    
+    /// Add the counts and file lists of another scan to these statistics
+    pub fn merge(&mut self, other: &ClassScanStats) {
+        self.total_files += other.total_files;
+        self.total_classes += other.total_classes;
+        self.empty_files += other.empty_files;
+        self.empty_file_paths.extend(other.empty_file_paths.iter().cloned());
+        self.files_with_classes += other.files_with_classes;
+        self.error_files += other.error_files;
+        self.error_file_paths.extend(other.error_file_paths.iter().cloned());
+        self.timeout_files += other.timeout_files;
+        self.timeout_file_paths.extend(other.timeout_file_paths.iter().cloned());
+        self.excluded_classes += other.excluded_classes;
+        self.blacklisted += other.blacklisted;
+        self.filtered += other.filtered;
+        self.duplicate_classes += other.duplicate_classes;
+        self.merged_declarations += other.merged_declarations;
+        
+        for (extension, stats) in &other.by_extension {
+            let merged = self.by_extension.entry(extension.clone()).or_default();
+            merged.files += stats.files;
+            merged.classes += stats.classes;
+            merged.errors += stats.errors;
+        }
+    }
+}
+
+/// Statistics for all scanned files sharing one extension
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtensionStats {
+    /// Number of files with this extension
+    pub files: usize,
+    
+    /// Number of classes found in files with this extension
+    pub classes: usize,
+    
+    /// Number of files with this extension that failed to parse
+    pub errors: usize,
+}
+
+/// How much of each class a scan captures
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ScanMode {
+    /// Capture names, parents and properties
+    #[default]
+    Full,
+    
+    /// Capture only names, parents and nesting, skipping property extraction,
+    /// for building inheritance maps cheaply
+    StructureOnly,
 }
 
 /// Configuration options for class scanning
@@ -64,6 +272,13 @@ pub struct ClassScanOptions {
     /// Maximum number of files to process
     pub max_files: Option<usize>,
     
+    /// Scan a random sample of this many files instead of all of them, for
+    /// representative timings over large trees. Applied before `max_files`.
+    pub sample_files: Option<usize>,
+    
+    /// Seed for `sample_files`, so the same sample can be drawn again
+    pub sample_seed: Option<u64>,
+    
     /// Timeout duration for parsing a single file (in seconds)
     pub parse_timeout_seconds: u64,
     
@@ -72,26 +287,446 @@ pub struct ClassScanOptions {
     
     /// Whether to use the optimized parser from cpp_parser
     pub use_optimized_parser: bool,
+    
+    /// Whether to convert CRLF line endings to LF before parsing and hashing
+    pub normalize_line_endings: bool,
+    
+    /// Flag parsed files that yield fewer than this many classes as suspicious
+    pub warn_below_classes: Option<usize>,
+    
+    /// Whether to collect `#define` constants from scanned files
+    pub collect_defines: bool,
+    
+    /// Whether to record byte spans of class keywords and names
+    pub capture_spans: bool,
+    
+    /// Whether to record the raw text matched for each class, to debug
+    /// unexpected names
+    pub capture_raw_matches: bool,
+    
+    /// Whether to capture metadata written between a class declaration and
+    /// its `{` or `;`, such as `access = 3`
+    pub capture_declaration_metadata: bool,
+    
+    /// Whether to parse identical file content only once per run, reusing the
+    /// classes for every other file with the same content hash. Saves work
+    /// when duplicated or symlinked mods appear under several roots.
+    pub cache_parsed_content: bool,
+    
+    /// Number of times to retry a file read that failed with a transient error
+    pub read_retries: u32,
+    
+    /// Drop classes inheriting from any of these parents, along with everything
+    /// that inherits from or is nested inside a dropped class
+    pub exclude_parents: Vec<String>,
+    
+    /// Drop classes whose whole name matches any of these regexes, such as
+    /// engine animation and state pseudo-classes
+    pub blacklist_patterns: Vec<String>,
+    
+    /// Drop classes whose name is shorter than this many characters, which
+    /// are usually noise matched in binary or macro-heavy files
+    pub min_class_name_len: usize,
+    
+    /// Keep only the first of several classes with the same name, container
+    /// and content, as produced by a header included from many files
+    pub deduplicate_identical: bool,
+    
+    /// Fold forward declarations into a definition in another file, such as
+    /// `class Car;` in a header into `class Car {...}` in a config. By default
+    /// declarations only merge with the definition in their own file.
+    pub merge_declarations_across_files: bool,
+    
+    /// Whether to look up each parent among the scanned classes and record
+    /// its file in `resolved_parent`
+    pub resolve_parents: bool,
+    
+    /// Truncate property values longer than this many characters, appending
+    /// `TRUNCATION_MARKER` and recording the property in `truncated_properties`
+    pub max_property_value_len: Option<usize>,
+    
+    /// Whether to capture `// @key value` annotations from comment lines
+    /// directly above each class
+    pub capture_annotations: bool,
+    
+    /// Algorithm used to hash file content for change detection
+    pub hash_algorithm: HashAlgorithm,
+    
+    /// How much of each class to capture
+    pub scan_mode: ScanMode,
+    
+    /// Fail the scan when more than this fraction of files, from 0.0 to 1.0,
+    /// failed to parse or timed out, as that points to a systemic problem
+    pub max_error_rate: Option<f64>,
+    
+    /// Store file paths relative to this directory, so databases don't depend
+    /// on where the files were scanned. Files outside it keep their full path.
+    pub path_root: Option<PathBuf>,
+    
+    /// Preprocessor symbols considered defined, so that classes in
+    /// `#ifdef`/`#ifndef` branches are kept or dropped accordingly
+    pub defined_symbols: HashSet<String>,
 }
 
+/// Appended to property values cut short by `max_property_value_len`
+pub const TRUNCATION_MARKER: &str = "...";
+
 impl Default for ClassScanOptions {
     fn default() -> Self {
         Self {
             verbose_errors: false,
             max_files: None,
+            sample_files: None,
+            sample_seed: None,
             parse_timeout_seconds: 10,
             parallel_threads: None,
             use_optimized_parser: true,
+            normalize_line_endings: false,
+            warn_below_classes: None,
+            collect_defines: false,
+            capture_spans: false,
+            capture_raw_matches: false,
+            capture_declaration_metadata: false,
+            cache_parsed_content: false,
+            read_retries: 0,
+            exclude_parents: Vec::new(),
+            blacklist_patterns: Vec::new(),
+            min_class_name_len: 1,
+            deduplicate_identical: false,
+            merge_declarations_across_files: false,
+            resolve_parents: false,
+            max_property_value_len: None,
+            capture_annotations: false,
+            hash_algorithm: HashAlgorithm::default(),
+            defined_symbols: HashSet::new(),
+            path_root: None,
+            scan_mode: ScanMode::default(),
+            max_error_rate: None,
         }
     }
 }
 
 /// Result of a class scanning operation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ClassScanResult {
     /// The processed classes found during scanning
     pub classes: Vec<ProcessedClass>,
     
     /// Statistics about the scanning process
     pub stats: ClassScanStats,
-} 
\ No newline at end of file
+    
+    /// Content hash of each successfully parsed file
+    pub file_hashes: HashMap<PathBuf, String>,
+    
+    /// Algorithm that produced `file_hashes`
+    pub hash_algorithm: HashAlgorithm,
+    
+    /// Parsed files that produced fewer classes than `warn_below_classes`
+    pub suspicious_files: Vec<PathBuf>,
+    
+    /// `#define` constants across all files, keeping the first value seen
+    pub defines: HashMap<String, String>,
+    
+    /// Defines given different values in different places
+    pub define_conflicts: Vec<DefineConflict>,
+    
+    /// Classes defined more than once in the same container with differing content
+    pub class_conflicts: Vec<ClassConflict>,
+    
+    /// Whether the scan was cancelled before all files were processed
+    pub cancelled: bool,
+    
+    /// Root that relative file paths were made relative to, from `path_root`
+    pub path_root: Option<PathBuf>,
+}
+
+/// A `#define` that was given more than one distinct value during a scan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DefineConflict {
+    /// Name of the macro
+    pub name: String,
+    
+    /// Every definition found, as (file, value)
+    pub definitions: Vec<(PathBuf, String)>,
+}
+
+/// A class defined more than once in the same container, with differing content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassConflict {
+    /// Name of the class
+    pub name: String,
+    
+    /// Names of the enclosing classes, outermost first
+    pub container_path: Vec<String>,
+    
+    /// File of each definition, in scan order
+    pub files: Vec<Option<PathBuf>>,
+}
+
+impl ClassScanResult {
+    /// Find a class by name
+    pub fn find(&self, name: &str) -> Option<&ProcessedClass> {
+        self.classes.iter().find(|c| c.name == name)
+    }
+    
+    /// Get the classes that directly inherit from `parent`
+    pub fn children_of(&self, parent: &str) -> Vec<&ProcessedClass> {
+        self.classes.iter()
+            .filter(|c| c.parent.as_deref() == Some(parent))
+            .collect()
+    }
+    
+    /// Get the names of all classes in scan order
+    pub fn names(&self) -> Vec<&str> {
+        self.classes.iter().map(|c| c.name.as_str()).collect()
+    }
+    
+    /// Format a multi-line, human-readable report of the scan.
+    ///
+    /// Errors aren't recorded with their causes, so the report lists the first
+    /// few failed files and the extensions with the most failures instead.
+    pub fn summary(&self) -> String {
+        const LISTED_FILES: usize = 5;
+        let stats = &self.stats;
+        
+        let mut lines = vec![
+            format!("Files scanned: {}", stats.total_files),
+            format!("Files with classes: {} ({} empty)", stats.files_with_classes, stats.empty_files),
+            format!("Classes found: {} ({:.1} per file)", stats.total_classes, stats.avg_classes_per_file()),
+            format!("Errors: {}", stats.error_files),
+            format!("Timeouts: {}", stats.timeout_files),
+            format!("Success rate: {:.1}%", stats.success_rate() * 100.0),
+        ];
+        if self.cancelled {
+            lines.push("Scan was cancelled before all files were processed".to_string());
+        }
+        
+        let mut failing_extensions: Vec<(&String, usize)> = stats.by_extension.iter()
+            .filter(|(_, ext)| ext.errors > 0)
+            .map(|(name, ext)| (name, ext.errors))
+            .collect();
+        failing_extensions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        if !failing_extensions.is_empty() {
+            lines.push("Errors by extension:".to_string());
+            lines.extend(failing_extensions.iter().map(|(name, errors)| format!("  .{}: {}", name, errors)));
+        }
+        
+        for (label, paths) in [("Failed files", &stats.error_file_paths), ("Timed out files", &stats.timeout_file_paths)] {
+            if paths.is_empty() {
+                continue;
+            }
+            lines.push(format!("{}:", label));
+            lines.extend(paths.iter().take(LISTED_FILES).map(|path| format!("  {}", path.display())));
+            if paths.len() > LISTED_FILES {
+                lines.push(format!("  ... and {} more", paths.len() - LISTED_FILES));
+            }
+        }
+        
+        lines.join("\n")
+    }
+    
+    /// Add the classes, statistics and file data of another scan to this one.
+    ///
+    /// Forward declarations are folded into definitions and class conflicts
+    /// are recomputed across both scans, following `options`: declarations
+    /// merge across files when `merge_declarations_across_files` is set, and
+    /// identical duplicates are dropped when `deduplicate_identical` is set.
+    pub fn merge(&mut self, other: ClassScanResult, options: &ClassScanOptions) {
+        if other.hash_algorithm != self.hash_algorithm {
+            warn!("Merging scan results hashed with {:?} into results hashed with {:?}",
+                other.hash_algorithm, self.hash_algorithm);
+        }
+        
+        if other.path_root != self.path_root {
+            warn!("Merging scan results relative to {:?} into results relative to {:?}",
+                other.path_root, self.path_root);
+        }
+        
+        self.stats.merge(&other.stats);
+        self.file_hashes.extend(other.file_hashes);
+        self.suspicious_files.extend(other.suspicious_files);
+        self.cancelled |= other.cancelled;
+        
+        for (name, value) in other.defines {
+            match self.defines.get(&name) {
+                Some(existing) if *existing != value => {
+                    warn!("Macro {} has different values in merged scans", name);
+                }
+                Some(_) => {}
+                None => {
+                    self.defines.insert(name, value);
+                }
+            }
+        }
+        for conflict in other.define_conflicts {
+            match self.define_conflicts.iter_mut().find(|c| c.name == conflict.name) {
+                Some(existing) => existing.definitions.extend(conflict.definitions),
+                None => self.define_conflicts.push(conflict),
+            }
+        }
+        self.define_conflicts.sort_by(|a, b| a.name.cmp(&b.name));
+        
+        let mut classes = std::mem::take(&mut self.classes);
+        classes.extend(other.classes);
+        
+        let before = classes.len();
+        let classes = merge_declarations(classes, options.merge_declarations_across_files);
+        let merged_declarations = before - classes.len();
+        self.stats.merged_declarations += merged_declarations;
+        self.stats.total_classes = self.stats.total_classes.saturating_sub(merged_declarations);
+        
+        let before = classes.len();
+        let (classes, class_conflicts) = resolve_duplicates(classes, options.deduplicate_identical);
+        let dropped = before - classes.len();
+        self.stats.duplicate_classes += dropped;
+        self.stats.total_classes = self.stats.total_classes.saturating_sub(dropped);
+        
+        self.classes = classes;
+        self.class_conflicts = class_conflicts;
+        self.stats.recount_classes_by_extension(&self.classes);
+    }
+    
+    /// Drop all but the first of classes with the same name, container and
+    /// content, returning how many were dropped
+    pub fn deduplicate(&mut self) -> usize {
+        let before = self.classes.len();
+        let (classes, class_conflicts) = resolve_duplicates(std::mem::take(&mut self.classes), true);
+        self.classes = classes;
+        self.class_conflicts = class_conflicts;
+        
+        let dropped = before - self.classes.len();
+        self.stats.duplicate_classes += dropped;
+        self.stats.total_classes = self.stats.total_classes.saturating_sub(dropped);
+        self.stats.recount_classes_by_extension(&self.classes);
+        dropped
+    }
+}
+
+/// Fold forward declarations into the definition of the same class in the
+/// same container, or into the first declaration when there is no definition.
+/// Only classes in the same file merge unless `across_files` is set.
+/// A definition without a parent takes the one given by its declaration.
+pub(crate) fn merge_declarations(mut classes: Vec<ProcessedClass>, across_files: bool) -> Vec<ProcessedClass> {
+    let merge_key = |class: &ProcessedClass| {
+        let file = if across_files { None } else { class.file_path.clone() };
+        (file, class.container_path.clone(), class.name.clone())
+    };
+    
+    let mut kept: HashMap<(Option<PathBuf>, Vec<String>, String), usize> = HashMap::new();
+    for (i, class) in classes.iter().enumerate() {
+        let key = merge_key(class);
+        match kept.entry(key) {
+            Entry::Vacant(slot) => {
+                slot.insert(i);
+            }
+            Entry::Occupied(mut slot) => {
+                if classes[*slot.get()].is_forward_declaration && !class.is_forward_declaration {
+                    slot.insert(i);
+                }
+            }
+        }
+    }
+    
+    let mut drop = vec![false; classes.len()];
+    for i in 0..classes.len() {
+        let target = kept[&merge_key(&classes[i])];
+        if i == target || !classes[i].is_forward_declaration {
+            continue;
+        }
+        drop[i] = true;
+        
+        let declared_parent = classes[i].parent.clone();
+        let target_class = &mut classes[target];
+        match (&target_class.parent, declared_parent) {
+            (None, Some(parent)) => target_class.parent = Some(parent),
+            (Some(defined), Some(declared)) if *defined != declared => {
+                warn!("Class {} is declared with parent {} but defined with parent {}",
+                    target_class.name, declared, defined);
+            }
+            _ => {}
+        }
+    }
+    
+    classes.into_iter()
+        .zip(drop)
+        .filter_map(|(class, dropped)| (!dropped).then_some(class))
+        .collect()
+}
+
+/// Find classes defined more than once in the same container. Duplicates
+/// whose parent and properties all match are not conflicts, and all but the
+/// first are dropped when `drop_identical` is set.
+pub(crate) fn resolve_duplicates(classes: Vec<ProcessedClass>, drop_identical: bool) -> (Vec<ProcessedClass>, Vec<ClassConflict>) {
+    let mut first_seen: HashMap<(&[String], &str), usize> = HashMap::new();
+    let mut duplicates: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, class) in classes.iter().enumerate() {
+        match first_seen.entry((class.container_path.as_slice(), class.name.as_str())) {
+            Entry::Occupied(first) => duplicates.entry(*first.get()).or_default().push(i),
+            Entry::Vacant(slot) => {
+                slot.insert(i);
+            }
+        }
+    }
+    
+    let same_content = |a: &ProcessedClass, b: &ProcessedClass| a.parent == b.parent && a.properties == b.properties;
+    
+    let mut drop = vec![false; classes.len()];
+    let mut conflicts = Vec::new();
+    for (first, others) in &duplicates {
+        let original = &classes[*first];
+        if others.iter().all(|&i| same_content(original, &classes[i])) {
+            if drop_identical {
+                for &i in others {
+                    drop[i] = true;
+                }
+            }
+        } else {
+            conflicts.push(ClassConflict {
+                name: original.name.clone(),
+                container_path: original.container_path.clone(),
+                files: std::iter::once(*first).chain(others.iter().copied())
+                    .map(|i| classes[i].file_path.clone())
+                    .collect(),
+            });
+        }
+    }
+    conflicts.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.container_path.cmp(&b.container_path)));
+    
+    let kept = classes.into_iter()
+        .zip(drop)
+        .filter_map(|(class, dropped)| (!dropped).then_some(class))
+        .collect();
+    (kept, conflicts)
+}
+
+/// A single run-level entry in the scan audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanAuditRecord {
+    /// When the scan finished
+    pub timestamp: DateTime<Utc>,
+    
+    /// Number of files processed
+    pub total_files: usize,
+    
+    /// Number of classes found
+    pub total_classes: usize,
+    
+    /// Number of files that failed to parse
+    pub error_files: usize,
+    
+    /// Number of files that timed out during parsing
+    pub timeout_files: usize,
+}
+
+/// Timing of a single file parsed by `ClassProcessor::benchmark_files`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileBench {
+    /// The parsed file
+    pub path: PathBuf,
+    
+    /// Time taken to read, hash and parse the file
+    pub duration: Duration,
+    
+    /// Number of classes found, or 0 if the file failed to parse
+    pub classes: usize,
+}
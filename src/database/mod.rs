@@ -1,8 +1,10 @@
 pub mod types;
 pub mod storage;
 pub mod operations;
+pub mod shared;
 
 // Re-export main types and functions for easier access
-pub use types::{ClassDatabase, ClassDatabaseStats, ClassEntry};
+pub use types::{ClassDatabase, ClassDatabaseStats, ClassDatabaseDetailedStats, ClassEntry, ClassTreeNode, DatabaseDelta, PropertyKind, SchemaViolation, StorageInfo, ValidationIssue};
 pub use operations::{DatabaseOperations, QueryOptions};
-pub use storage::DatabaseStorage; 
\ No newline at end of file
+pub use storage::DatabaseStorage;
+pub use shared::SharedDatabase; 
\ No newline at end of file
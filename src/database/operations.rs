@@ -1,13 +1,17 @@
-use std::path::Path;
-use std::collections::HashSet;
-use anyhow::Result;
-use log::{info, warn};
-use chrono::Utc;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use anyhow::{Result, Context, bail};
+use log::{info, warn, debug};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
 use serde_json;
 
-use crate::class::types::ClassScanResult;
-use crate::utils::{file_utils, hash_utils};
-use super::types::{ClassDatabase, ClassDatabaseStats, ClassEntry};
+use crate::class::types::{ClassScanResult, SourceKind};
+use crate::utils::file_utils;
+use crate::utils::hash_utils::{self, HashAlgorithm};
+use super::types::{ClassDatabase, ClassDatabaseStats, ClassDatabaseDetailedStats, ClassEntry, ClassTreeNode, DatabaseDelta, PropertyKind, SchemaViolation, StorageInfo, root_id};
 use super::storage::DatabaseStorage;
 
 /// Options for querying the database
@@ -22,16 +26,127 @@ pub struct QueryOptions {
     /// Filter classes by property value
     pub property_value: Option<String>,
     
+    /// Only match classes that define every one of these properties
+    pub required_properties: Vec<String>,
+    
+    /// Filter classes by the kind of file they were found in
+    pub source_kind: Option<SourceKind>,
+    
+    /// Only match classes whose file path matches this glob, e.g. `**/weapons/*.hpp`
+    pub file_pattern: Option<String>,
+    
+    /// Skip classes whose file path matches this glob, e.g. `**/test/**`.
+    /// Applied together with `file_pattern`, so both must agree.
+    pub exclude_file_pattern: Option<String>,
+    
+    /// Skip bare `class Name;` forward declarations, returning only classes
+    /// with a body
+    pub definitions_only: bool,
+    
+    /// Number of results to skip, after sorting and before `limit`
+    pub offset: Option<usize>,
+    
     /// Maximum number of results to return
     pub limit: Option<usize>,
     
-    /// Sort results by this field
+    /// Sort results by this field: `name`, `added_at`, `updated_at`, or
+    /// `prop:<name>` to sort by a property value. Results are ordered by name when unset.
     pub sort_by: Option<String>,
     
     /// Sort in descending order
     pub descending: bool,
 }
 
+/// How a class sorts by a property value
+enum PropertySortKey<'a> {
+    Number(f64),
+    Text(&'a str),
+    Missing,
+}
+
+impl PropertySortKey<'_> {
+    /// Numbers come first, then other text, then classes without the property
+    fn rank(&self) -> u8 {
+        match self {
+            PropertySortKey::Number(_) => 0,
+            PropertySortKey::Text(_) => 1,
+            PropertySortKey::Missing => 2,
+        }
+    }
+}
+
+/// Compare classes by the value of `prop`. Only values of the same kind are
+/// reversed by `descending`, so non-numeric and missing values always sort last.
+fn compare_by_property(a: &ClassEntry, b: &ClassEntry, prop: &str, descending: bool) -> std::cmp::Ordering {
+    let ordering = match (property_sort_key(a, prop), property_sort_key(b, prop)) {
+        (PropertySortKey::Number(x), PropertySortKey::Number(y)) => x.total_cmp(&y),
+        (PropertySortKey::Text(x), PropertySortKey::Text(y)) => x.cmp(y),
+        (a_key, b_key) => return a_key.rank().cmp(&b_key.rank()),
+    };
+    if descending { ordering.reverse() } else { ordering }
+}
+
+/// Get the sort key of `prop` on an entry
+fn property_sort_key<'a>(entry: &'a ClassEntry, prop: &str) -> PropertySortKey<'a> {
+    match entry.class.properties.iter().find(|(name, _)| name == prop) {
+        Some((_, value)) => match value.trim().parse::<f64>() {
+            Ok(number) => PropertySortKey::Number(number),
+            Err(_) => PropertySortKey::Text(value),
+        },
+        None => PropertySortKey::Missing,
+    }
+}
+
+/// Match a path against a glob where `*` stays within a single path component
+fn matches_glob(pattern: &glob::Pattern, path: &Path) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    pattern.matches_path_with(path, options)
+}
+
+/// Name of the index file written by `save_sharded`
+pub const SHARD_INDEX_FILE: &str = "index.json";
+
+/// Shard holding classes that aren't nested in any container
+const ROOT_SHARD: &str = "root";
+
+/// Index of a sharded database, tying the per-section files together
+#[derive(Debug, Serialize, Deserialize)]
+struct ShardIndex {
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    version: String,
+    /// Section name to its shard file
+    shards: BTreeMap<String, ShardRecord>,
+    file_classes: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    file_roots: BTreeMap<String, String>,
+}
+
+/// A shard file listed in a `ShardIndex`
+#[derive(Debug, Serialize, Deserialize)]
+struct ShardRecord {
+    /// File name, relative to the index
+    file: String,
+    
+    /// Hash of the file's content, verified on load
+    checksum: String,
+}
+
+/// Get the shard file name for a section. Class names are case-insensitive
+/// and so are some file systems, so the name is case-folded and sanitized,
+/// with a hash of the exact section name keeping `CfgVehicles` and
+/// `cfgVehicles` apart.
+fn shard_file_name(section: &str) -> String {
+    let folded: String = section.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    let hash = HashAlgorithm::Xxh3.hash_string(section);
+    format!("section_{}_{}.json", folded, &hash[..8])
+}
+
 /// Database operations for querying and updating the database
 #[derive(Debug)]
 pub struct DatabaseOperations {
@@ -45,7 +160,12 @@ pub struct DatabaseOperations {
 impl DatabaseOperations {
     /// Create a new database operations instance
     pub fn new(db_path: impl AsRef<Path>) -> Result<Self> {
-        let storage = DatabaseStorage::new(db_path);
+        Self::from_storage(DatabaseStorage::new(db_path))
+    }
+    
+    /// Create a database operations instance loading from configured storage,
+    /// e.g. one that recovers from corruption
+    pub fn from_storage(storage: DatabaseStorage) -> Result<Self> {
         let db = storage.load()?;
         
         Ok(Self {
@@ -54,6 +174,13 @@ impl DatabaseOperations {
         })
     }
     
+    /// Store the database at a different path from now on, e.g. to give an
+    /// imported database a file. Nothing is written until `save` is called.
+    pub fn with_db_path(mut self, db_path: impl AsRef<Path>) -> Self {
+        self.storage = DatabaseStorage::new(db_path);
+        self
+    }
+    
     /// Get a reference to the database
     pub fn database(&self) -> &ClassDatabase {
         &self.db
@@ -66,11 +193,75 @@ impl DatabaseOperations {
     
     /// Save the database to disk
     pub fn save(&self) -> Result<()> {
+        if self.storage.path().as_os_str().is_empty() {
+            bail!("Database has no file to save to; set one with with_db_path");
+        }
         self.storage.save(&self.db)
     }
     
+    /// Take a copy of the current database, to `restore` later
+    pub fn snapshot(&self) -> ClassDatabase {
+        self.db.clone()
+    }
+    
+    /// Replace the loaded database with an earlier snapshot
+    pub fn restore(&mut self, snapshot: ClassDatabase) {
+        info!("Restoring database snapshot with {} classes", snapshot.entries.len());
+        self.db = snapshot;
+    }
+    
+    /// Save the current database to a snapshot file, separate from the database file
+    pub fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<()> {
+        DatabaseStorage::new(path).save(&self.db)
+    }
+    
+    /// Replace the loaded database with one saved by `save_snapshot`
+    pub fn load_snapshot(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let storage = DatabaseStorage::new(path);
+        if !storage.exists() {
+            bail!("Snapshot file {} does not exist", storage.path().display());
+        }
+        self.restore(storage.load()?);
+        Ok(())
+    }
+    
+    /// Run several updates as one transaction. The changes made by `f` are
+    /// kept and saved only if it returns `Ok`; on any error, including a failed
+    /// save, the database is rolled back to how it was before.
+    pub fn transaction<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        let snapshot = self.snapshot();
+        
+        let result = f(self).and_then(|value| {
+            self.save()?;
+            Ok(value)
+        });
+        
+        if result.is_err() {
+            warn!("Transaction failed, discarding its changes");
+            self.restore(snapshot);
+        }
+        result
+    }
+    
     /// Update the database with new scan results
     pub fn update_with_scan_results(&mut self, scan_result: ClassScanResult) -> Result<ClassDatabaseStats> {
+        self.update_with_scan_results_with_progress(scan_result, |_, _| {})
+    }
+    
+    /// Update the database with new scan results, calling `progress` with
+    /// `(processed, total)` classes as the update goes; the last call always
+    /// reports `(total, total)`
+    pub fn update_with_scan_results_with_progress<F>(
+        &mut self,
+        scan_result: ClassScanResult,
+        mut progress: F,
+    ) -> Result<ClassDatabaseStats>
+    where
+        F: FnMut(usize, usize),
+    {
         info!("Updating database with {} classes", scan_result.classes.len());
         
         let mut stats = ClassDatabaseStats::default();
@@ -83,36 +274,98 @@ impl DatabaseOperations {
         let mut processed_files = HashSet::new();
         
         // Process each class
-        for class in scan_result.classes {
+        let ClassScanResult { classes, file_hashes, hash_algorithm, path_root, .. } = scan_result;
+        
+        // Files in this scan get their class lists rebuilt from scratch, so
+        // classes that moved away or were deleted don't linger
+        let scanned_files: HashSet<String> = classes.iter()
+            .filter_map(|class| class.file_path.as_ref())
+            .chain(file_hashes.keys())
+            .map(file_utils::path_key)
+            .collect();
+        
+        // A relative path is only unique within its root, so refuse to rebuild
+        // a file stored from a different root rather than merge the two. Files
+        // without a recorded root predate root tracking and are taken as-is.
+        let root = path_root.as_deref().map_or_else(String::new, root_id);
+        let relative_files: Vec<&String> = scanned_files.iter()
+            .filter(|file| Path::new(file.as_str()).is_relative())
+            .collect();
+        let mut collisions: Vec<&str> = relative_files.iter()
+            .filter(|file| self.db.file_classes.get(file.as_str()).is_some_and(|names| !names.is_empty()))
+            .filter(|file| self.db.file_roots.get(file.as_str()).is_some_and(|stored| *stored != root))
+            .map(|file| file.as_str())
+            .collect();
+        if !collisions.is_empty() {
+            collisions.sort();
+            bail!("{} files scanned from {} share a relative path with files stored from a different root ({}); \
+                scan both from a common root instead",
+                collisions.len(),
+                path_root.as_deref().map_or("no root".to_string(), |root| root.display().to_string()),
+                collisions.join(", "));
+        }
+        
+        for file in &scanned_files {
+            self.db.file_classes.remove(file);
+        }
+        for file in relative_files {
+            self.db.file_roots.insert(file.clone(), root.clone());
+        }
+        
+        let total = classes.len();
+        for (processed, class) in classes.into_iter().enumerate() {
+            progress(processed, total);
+            
             // Add class to seen set
             seen_classes.insert(class.name.clone());
             
             // Track the file
             if let Some(file_path) = &class.file_path {
-                let path_str = file_path.to_string_lossy().to_string();
+                let path_str = file_utils::path_key(file_path);
                 processed_files.insert(path_str.clone());
                 
-                // Calculate file hash using hash_utils
-                let file_hash = hash_utils::hash_file(file_path).unwrap_or_else(|_| "unknown".to_string());
+                // Prefer the hash computed during the scan, falling back to hashing the file
+                let file_hash = match file_hashes.get(file_path) {
+                    Some(hash) => hash.clone(),
+                    None => hash_algorithm.hash_file(file_path).unwrap_or_else(|_| "unknown".to_string()),
+                };
                 
                 // Update file_classes map
-                let class_names = self.db.file_classes.entry(path_str).or_insert_with(Vec::new);
+                let class_names = self.db.file_classes.entry(path_str).or_default();
                 if !class_names.contains(&class.name) {
                     class_names.push(class.name.clone());
                 }
                 
                 // Check if class already exists
                 if let Some(existing) = self.db.entries.get(&class.name) {
-                    // Check if the file hash has changed
-                    if existing.file_hash != file_hash {
+                    // A forward declaration never replaces a full definition
+                    if class.is_forward_declaration && !existing.class.is_forward_declaration {
+                        continue;
+                    }
+                    
+                    // Hashes from different algorithms can't be compared, so fall
+                    // back to comparing the class itself
+                    let changed = if existing.hash_algorithm == hash_algorithm {
+                        existing.file_hash != file_hash
+                    } else {
+                        existing.class != class
+                    };
+                    
+                    if changed {
                         // Update the class
                         self.db.entries.insert(class.name.clone(), ClassEntry {
                             class,
                             added_at: existing.added_at,
                             updated_at: now,
                             file_hash,
+                            hash_algorithm,
                         });
                         stats.updated_classes += 1;
+                    } else if existing.hash_algorithm != hash_algorithm {
+                        // Unchanged, but record the hash under the new algorithm
+                        let entry = self.db.entries.get_mut(&class.name).unwrap();
+                        entry.file_hash = file_hash;
+                        entry.hash_algorithm = hash_algorithm;
                     }
                 } else {
                     // Add new class
@@ -121,6 +374,7 @@ impl DatabaseOperations {
                         added_at: now,
                         updated_at: now,
                         file_hash,
+                        hash_algorithm,
                     });
                     stats.added_classes += 1;
                 }
@@ -131,10 +385,12 @@ impl DatabaseOperations {
                     added_at: now,
                     updated_at: now,
                     file_hash: "unknown".to_string(),
+                    hash_algorithm,
                 });
                 stats.added_classes += 1;
             }
         }
+        progress(total, total);
         
         // Update database metadata
         self.db.updated_at = now;
@@ -152,8 +408,45 @@ impl DatabaseOperations {
         Ok(stats)
     }
     
+    /// Remove the classes recorded for a file that no longer exists, returning
+    /// their names sorted. Classes that have since moved to another file are kept.
+    pub fn remove_classes_in_file(&mut self, file_path: impl AsRef<Path>) -> Vec<String> {
+        let file_path = file_path.as_ref();
+        self.db.file_roots.remove(&file_utils::path_key(file_path));
+        let Some(class_names) = self.db.file_classes.remove(&file_utils::path_key(file_path)) else {
+            return Vec::new();
+        };
+        
+        let mut removed: Vec<String> = class_names.into_iter()
+            .filter(|name| self.db.entries.get(name)
+                .is_some_and(|entry| entry.class.file_path.as_deref() == Some(file_path)))
+            .collect();
+        for name in &removed {
+            self.db.entries.remove(name);
+        }
+        
+        removed.sort();
+        self.db.updated_at = Utc::now();
+        removed
+    }
+    
     /// Query the database for classes matching the given options
     pub fn query(&self, options: &QueryOptions) -> Vec<&ClassEntry> {
+        let file_pattern = match options.file_pattern.as_deref().map(glob::Pattern::new).transpose() {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                warn!("Invalid file pattern {:?}: {}", options.file_pattern, e);
+                return Vec::new();
+            }
+        };
+        let exclude_file_pattern = match options.exclude_file_pattern.as_deref().map(glob::Pattern::new).transpose() {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                warn!("Invalid exclude file pattern {:?}: {}", options.exclude_file_pattern, e);
+                return Vec::new();
+            }
+        };
+        
         let mut results: Vec<&ClassEntry> = self.db.entries.values()
             .filter(|entry| {
                 // Filter by parent
@@ -168,23 +461,51 @@ impl DatabaseOperations {
                 }
                 
                 // Filter by property name
-                if let Some(prop_name) = &options.property_name {
-                    if !entry.class.properties.iter().any(|(name, _)| name == prop_name) {
-                        return false;
-                    }
+                if let Some(prop_name) = &options.property_name
+                    && !entry.class.properties.iter().any(|(name, _)| name == prop_name) {
+                    return false;
                 }
                 
                 // Filter by property value
-                if let Some(prop_value) = &options.property_value {
-                    if !entry.class.properties.iter().any(|(_, value)| value == prop_value) {
-                        return false;
-                    }
+                if let Some(prop_value) = &options.property_value
+                    && !entry.class.properties.iter().any(|(_, value)| **value == **prop_value) {
+                    return false;
+                }
+                
+                // Filter by source kind
+                if let Some(source_kind) = options.source_kind
+                    && entry.class.source_kind != source_kind {
+                    return false;
+                }
+                
+                // Filter by file path pattern
+                if let Some(pattern) = &file_pattern
+                    && !entry.class.file_path.as_ref().is_some_and(|path| matches_glob(pattern, path)) {
+                    return false;
+                }
+                if let Some(pattern) = &exclude_file_pattern
+                    && entry.class.file_path.as_ref().is_some_and(|path| matches_glob(pattern, path)) {
+                    return false;
+                }
+                
+                if options.definitions_only && entry.class.is_forward_declaration {
+                    return false;
+                }
+                
+                // Filter by required properties
+                if !options.required_properties.iter()
+                    .all(|required| entry.class.properties.iter().any(|(name, _)| name == required)) {
+                    return false;
                 }
                 
                 true
             })
             .collect();
         
+        // Order by name first so results are deterministic; the requested sort
+        // is stable, so ties keep this order
+        results.sort_by(|a, b| a.class.name.cmp(&b.class.name));
+        
         // Sort results if requested
         if let Some(sort_by) = &options.sort_by {
             match sort_by.as_str() {
@@ -215,30 +536,95 @@ impl DatabaseOperations {
                         }
                     });
                 }
-                _ => {
-                    warn!("Unknown sort field: {}", sort_by);
-                }
+                field => match field.strip_prefix("prop:") {
+                    Some(prop) => {
+                        results.sort_by(|a, b| compare_by_property(a, b, prop, options.descending));
+                    }
+                    None => {
+                        warn!("Unknown sort field: {}", sort_by);
+                    }
+                },
             }
         }
         
+        // Skip the first results for pagination
+        if let Some(offset) = options.offset {
+            results.drain(..offset.min(results.len()));
+        }
+        
         // Apply limit if requested
-        if let Some(limit) = options.limit {
-            if limit < results.len() {
-                results.truncate(limit);
-            }
+        if let Some(limit) = options.limit
+            && limit < results.len() {
+            results.truncate(limit);
         }
         
         results
     }
     
+    /// Run a query and bucket the results by parent, with parentless classes
+    /// under `None`. Each bucket keeps the query's order.
+    pub fn query_grouped_by_parent(&self, options: &QueryOptions) -> BTreeMap<Option<String>, Vec<&ClassEntry>> {
+        let mut groups: BTreeMap<Option<String>, Vec<&ClassEntry>> = BTreeMap::new();
+        for entry in self.query(options) {
+            groups.entry(entry.class.parent.clone()).or_default().push(entry);
+        }
+        groups
+    }
+    
     /// Get a class by name
     pub fn get_class(&self, name: &str) -> Option<&ClassEntry> {
         self.db.entries.get(name)
     }
     
+    /// Look up many classes at once, returning the found entries and the missing names
+    pub fn get_classes(&self, names: &[String]) -> (Vec<&ClassEntry>, Vec<String>) {
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        
+        for name in names {
+            match self.db.entries.get(name) {
+                Some(entry) => found.push(entry),
+                None => missing.push(name.clone()),
+            }
+        }
+        
+        (found, missing)
+    }
+    
+    /// Map each lowercased class name to the original names sharing it, sorted.
+    /// Names that differ only by case end up in the same list.
+    pub fn lowercase_index(&self) -> BTreeMap<String, Vec<String>> {
+        let mut index: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for name in self.db.entries.keys() {
+            index.entry(name.to_lowercase()).or_default().push(name.clone());
+        }
+        for names in index.values_mut() {
+            names.sort();
+        }
+        index
+    }
+    
+    /// Get the groups of class names that differ only by case
+    pub fn case_collisions(&self) -> Vec<Vec<String>> {
+        self.lowercase_index().into_values()
+            .filter(|names| names.len() > 1)
+            .collect()
+    }
+    
+    /// Get every class whose name matches `name` ignoring case, sorted by name
+    pub fn get_classes_ignore_case(&self, name: &str) -> Vec<&ClassEntry> {
+        let lowercase = name.to_lowercase();
+        let mut matches: Vec<&ClassEntry> = self.db.entries.iter()
+            .filter(|(key, _)| key.to_lowercase() == lowercase)
+            .map(|(_, entry)| entry)
+            .collect();
+        matches.sort_by(|a, b| a.class.name.cmp(&b.class.name));
+        matches
+    }
+    
     /// Get all classes in a file
     pub fn get_classes_in_file(&self, file_path: impl AsRef<Path>) -> Vec<&ClassEntry> {
-        let path_str = file_path.as_ref().to_string_lossy().to_string();
+        let path_str = file_utils::path_key(file_path);
         
         if let Some(class_names) = self.db.file_classes.get(&path_str) {
             class_names.iter()
@@ -249,6 +635,138 @@ impl DatabaseOperations {
         }
     }
     
+    /// Get the files a class was scanned from, sorted. Besides the file of the
+    /// stored entry, this includes every other file listing the class, such as
+    /// one holding a duplicate definition.
+    pub fn files_defining(&self, class_name: &str) -> Vec<PathBuf> {
+        let mut files: BTreeSet<PathBuf> = self.db.file_classes.iter()
+            .filter(|(_, class_names)| class_names.iter().any(|name| name == class_name))
+            .map(|(file, _)| PathBuf::from(file))
+            .collect();
+        
+        if let Some(file_path) = self.db.entries.get(class_name).and_then(|entry| entry.class.file_path.clone()) {
+            files.insert(file_path);
+        }
+        
+        files.into_iter().collect()
+    }
+    
+    /// Get every distinct parent name, sorted, whether or not it is defined in the database
+    pub fn all_parents(&self) -> Vec<String> {
+        self.db.entries.values()
+            .filter_map(|entry| entry.class.parent.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+    
+    /// Get every class that no other class inherits from, sorted by name
+    pub fn leaf_classes(&self) -> Vec<&ClassEntry> {
+        let parents: HashSet<&str> = self.db.entries.values()
+            .filter_map(|entry| entry.class.parent.as_deref())
+            .collect();
+        
+        let mut leaves: Vec<&ClassEntry> = self.db.entries.values()
+            .filter(|entry| !parents.contains(entry.class.name.as_str()))
+            .collect();
+        leaves.sort_by(|a, b| a.class.name.cmp(&b.class.name));
+        leaves
+    }
+    
+    /// Get every class declared as its own parent, like `class Foo: Foo`,
+    /// sorted by name. Names are compared ignoring case, as the engine does.
+    pub fn self_inheriting(&self) -> Vec<&ClassEntry> {
+        let mut classes: Vec<&ClassEntry> = self.db.entries.values()
+            .filter(|entry| entry.class.parent.as_deref()
+                .is_some_and(|parent| parent.eq_ignore_ascii_case(&entry.class.name)))
+            .collect();
+        classes.sort_by(|a, b| a.class.name.cmp(&b.class.name));
+        classes
+    }
+    
+    /// Get the `top_n` most common property names across all classes, with
+    /// the number of classes assigning each, most common first
+    pub fn property_frequency(&self, top_n: usize) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for entry in self.db.entries.values() {
+            for (name, _) in &entry.class.properties {
+                *counts.entry(name.as_str()).or_default() += 1;
+            }
+        }
+        
+        let mut frequency: Vec<(String, usize)> = counts.into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        frequency.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        frequency.truncate(top_n);
+        frequency
+    }
+    
+    /// Get the classes that have a parent and set `prop` themselves, overriding
+    /// whatever they inherit, sorted by name
+    pub fn classes_overriding_property(&self, prop: &str) -> Vec<&ClassEntry> {
+        let mut overriding: Vec<&ClassEntry> = self.db.entries.values()
+            .filter(|entry| entry.class.parent.is_some())
+            .filter(|entry| entry.class.properties.iter().any(|(name, _)| name == prop))
+            .collect();
+        overriding.sort_by(|a, b| a.class.name.cmp(&b.class.name));
+        overriding
+    }
+    
+    /// Count classes per source root, assigning each class to the deepest root
+    /// containing its file. Classes outside every root, or without a file, are
+    /// counted under an empty path.
+    pub fn counts_by_root(&self, roots: &[PathBuf]) -> HashMap<PathBuf, usize> {
+        let mut counts = HashMap::new();
+        for entry in self.db.entries.values() {
+            let root = entry.class.file_path.as_ref()
+                .and_then(|file| roots.iter()
+                    .filter(|root| file.starts_with(root))
+                    .max_by_key(|root| root.components().count()))
+                .cloned()
+                .unwrap_or_default();
+            *counts.entry(root).or_default() += 1;
+        }
+        counts
+    }
+    
+    /// Group the classes that don't set `prop` themselves by their parent,
+    /// with the class names sorted
+    pub fn missing_property_report(&self, prop: &str) -> HashMap<Option<String>, Vec<String>> {
+        let mut report: HashMap<Option<String>, Vec<String>> = HashMap::new();
+        for entry in self.db.entries.values() {
+            if !entry.class.properties.iter().any(|(name, _)| name == prop) {
+                report.entry(entry.class.parent.clone()).or_default().push(entry.class.name.clone());
+            }
+        }
+        for names in report.values_mut() {
+            names.sort();
+        }
+        report
+    }
+    
+    /// Check every class's properties against the kinds required by `schema`,
+    /// keyed by property name. Classes that don't define a property aren't
+    /// checked for it. Violations are ordered by class name.
+    pub fn validate_against_schema(&self, schema: &HashMap<String, PropertyKind>) -> Vec<SchemaViolation> {
+        let mut entries: Vec<&ClassEntry> = self.db.entries.values().collect();
+        entries.sort_by(|a, b| a.class.name.cmp(&b.class.name));
+        
+        entries.into_iter()
+            .flat_map(|entry| entry.class.properties.iter().filter_map(move |(name, value)| {
+                let expected = *schema.get(name)?;
+                let quoted = entry.class.quoted_properties.contains(name);
+                (!expected.accepts_captured(value, quoted)).then(|| SchemaViolation {
+                    class_name: entry.class.name.clone(),
+                    property: name.clone(),
+                    expected,
+                    found: PropertyKind::of_captured(value, quoted),
+                    value: value.to_string(),
+                })
+            }))
+            .collect()
+    }
+    
     /// Get database statistics
     pub fn get_stats(&self) -> ClassDatabaseStats {
         ClassDatabaseStats {
@@ -257,4 +775,595 @@ impl DatabaseOperations {
             ..ClassDatabaseStats::default()
         }
     }
-} 
\ No newline at end of file
+    
+    /// Derive totals from the current entries alone, ignoring how the database
+    /// got here. Unlike `get_stats`, files count only if they hold a stored
+    /// class, whether indexed in `file_classes` or recorded on an entry, so
+    /// stale or hand-edited index lists don't inflate the total.
+    pub fn recompute_stats(&self) -> ClassDatabaseStats {
+        let indexed = self.db.file_classes.iter()
+            .filter(|(_, class_names)| class_names.iter().any(|name| self.db.entries.contains_key(name)))
+            .map(|(file, _)| file.clone());
+        let recorded = self.db.entries.values()
+            .filter_map(|entry| entry.class.file_path.as_ref())
+            .map(file_utils::path_key);
+        let files: HashSet<String> = indexed.chain(recorded).collect();
+        
+        ClassDatabaseStats {
+            total_classes: self.db.entries.len(),
+            total_files: files.len(),
+            ..ClassDatabaseStats::default()
+        }
+    }
+    
+    /// Get a structural breakdown of the class hierarchy
+    pub fn stats_detailed(&self, top_n: usize) -> ClassDatabaseDetailedStats {
+        // Count direct children per parent name, including external parents
+        let mut child_counts: HashMap<&str, usize> = HashMap::new();
+        for entry in self.db.entries.values() {
+            if let Some(parent) = &entry.class.parent {
+                *child_counts.entry(parent.as_str()).or_default() += 1;
+            }
+        }
+        
+        let root_classes = self.db.entries.values()
+            .filter(|entry| entry.class.parent.is_none())
+            .count();
+        
+        let leaf_classes = self.db.entries.keys()
+            .filter(|name| !child_counts.contains_key(name.as_str()))
+            .count();
+        
+        let max_depth = self.db.entries.keys()
+            .map(|name| self.inheritance_depth(name))
+            .max()
+            .unwrap_or(0);
+        
+        let mut top_parents: Vec<(String, usize)> = child_counts.into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        top_parents.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_parents.truncate(top_n);
+        
+        ClassDatabaseDetailedStats {
+            totals: self.get_stats(),
+            root_classes,
+            leaf_classes,
+            max_depth,
+            top_parents,
+        }
+    }
+    
+    /// Get a class's effective properties: its own merged with those inherited
+    /// from its ancestors, with values closer to the class winning. Inherited
+    /// properties come first, in the order the most distant ancestor declares
+    /// them. The walk stops at a missing parent or a cycle.
+    pub fn effective_properties(&self, name: &str) -> Vec<(String, String)> {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current = self.db.entries.get(name);
+        
+        while let Some(entry) = current {
+            if !visited.insert(entry.class.name.as_str()) {
+                warn!("Inheritance cycle at class {} while resolving {}", entry.class.name, name);
+                break;
+            }
+            chain.push(entry);
+            current = entry.class.parent.as_ref().and_then(|parent| self.db.entries.get(parent));
+        }
+        
+        let mut properties: Vec<(String, String)> = Vec::new();
+        for entry in chain.iter().rev() {
+            for (prop, value) in &entry.class.properties {
+                match properties.iter_mut().find(|(existing, _)| existing == prop) {
+                    Some((_, existing)) => *existing = value.to_string(),
+                    None => properties.push((prop.clone(), value.to_string())),
+                }
+            }
+        }
+        
+        properties
+    }
+    
+    /// Count the parent links above a class, stopping at cycles
+    fn inheritance_depth(&self, name: &str) -> usize {
+        let mut depth = 0;
+        let mut visited = HashSet::new();
+        let mut current = self.db.entries.get(name);
+        
+        while let Some(entry) = current {
+            if !visited.insert(entry.class.name.as_str()) {
+                break;
+            }
+            match &entry.class.parent {
+                Some(parent) => {
+                    depth += 1;
+                    current = self.db.entries.get(parent);
+                }
+                None => break,
+            }
+        }
+        
+        depth
+    }
+    
+    /// Make identical property values share a single allocation.
+    ///
+    /// Values loaded from disk are separate allocations even when equal, so this
+    /// is worth running after `load` on large databases. Returns the number of
+    /// values that were replaced by a shared copy.
+    pub fn intern_strings(&mut self) -> usize {
+        let mut pool: HashSet<Arc<str>> = HashSet::new();
+        let mut deduplicated = 0;
+        
+        for entry in self.db.entries.values_mut() {
+            for (_, value) in entry.class.properties.iter_mut() {
+                match pool.get(value) {
+                    Some(shared) => {
+                        if !Arc::ptr_eq(shared, value) {
+                            *value = shared.clone();
+                            deduplicated += 1;
+                        }
+                    }
+                    None => {
+                        pool.insert(value.clone());
+                    }
+                }
+            }
+        }
+        
+        debug!("Interned property values, {} duplicates now shared", deduplicated);
+        deduplicated
+    }
+    
+    /// Remove stale references from `file_classes`.
+    ///
+    /// Class names that no longer exist in `entries` are dropped, then files
+    /// left without any classes are removed. `entries` is never modified.
+    /// Returns the number of stale class names and empty files removed.
+    pub fn compact(&mut self) -> usize {
+        let entries = &self.db.entries;
+        let mut removed = 0;
+        
+        for class_names in self.db.file_classes.values_mut() {
+            let before = class_names.len();
+            class_names.retain(|name| entries.contains_key(name));
+            removed += before - class_names.len();
+        }
+        
+        let before = self.db.file_classes.len();
+        self.db.file_classes.retain(|_, class_names| !class_names.is_empty());
+        removed += before - self.db.file_classes.len();
+        
+        info!("Compacted database, removed {} stale references", removed);
+        removed
+    }
+    
+    /// Report the on-disk size and an estimate of the in-memory size of the database
+    pub fn storage_info(&self) -> StorageInfo {
+        let entry_bytes: usize = self.db.entries.iter()
+            .map(|(key, entry)| {
+                let class = &entry.class;
+                key.len()
+                    + class.name.len()
+                    + class.parent.as_ref().map_or(0, |p| p.len())
+                    + class.properties.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
+                    + class.container_path.iter().map(|c| c.len()).sum::<usize>()
+                    + class.file_path.as_ref().map_or(0, |p| p.as_os_str().len())
+                    + entry.file_hash.len()
+            })
+            .sum();
+        
+        let file_class_bytes: usize = self.db.file_classes.iter()
+            .map(|(path, names)| path.len() + names.iter().map(|n| n.len()).sum::<usize>())
+            .sum();
+        
+        StorageInfo {
+            file_bytes: self.storage.file_size(),
+            estimated_memory_bytes: entry_bytes + file_class_bytes,
+        }
+    }
+    
+    /// Rename a class and update everything that refers to it by name.
+    ///
+    /// Children whose `parent` pointed at `old`, classes nested inside it and
+    /// the `file_classes` lists all follow the rename. Returns the number of
+    /// references updated, not counting the renamed entry itself. Renaming a
+    /// class to its own name changes nothing.
+    pub fn rename_class(&mut self, old: &str, new: &str) -> Result<usize> {
+        if old == new {
+            if !self.db.entries.contains_key(old) {
+                bail!("Cannot rename {}: class not found", old);
+            }
+            return Ok(0);
+        }
+        if self.db.entries.contains_key(new) {
+            bail!("Cannot rename {} to {}: a class named {} already exists", old, new, new);
+        }
+        let Some(mut entry) = self.db.entries.remove(old) else {
+            bail!("Cannot rename {}: class not found", old);
+        };
+        
+        let now = Utc::now();
+        entry.class.name = new.to_string();
+        entry.updated_at = now;
+        self.db.entries.insert(new.to_string(), entry);
+        
+        let mut updated = 0;
+        for entry in self.db.entries.values_mut() {
+            let mut touched = false;
+            
+            if entry.class.parent.as_deref() == Some(old) {
+                entry.class.parent = Some(new.to_string());
+                touched = true;
+                updated += 1;
+            }
+            
+            for container in entry.class.container_path.iter_mut().filter(|c| *c == old) {
+                *container = new.to_string();
+                touched = true;
+                updated += 1;
+            }
+            
+            if touched {
+                entry.updated_at = now;
+            }
+        }
+        
+        for class_names in self.db.file_classes.values_mut() {
+            for name in class_names.iter_mut().filter(|n| *n == old) {
+                *name = new.to_string();
+                updated += 1;
+            }
+        }
+        
+        self.db.updated_at = now;
+        info!("Renamed class {} to {}, updated {} references", old, new, updated);
+        Ok(updated)
+    }
+    
+    /// Mark the named entries as current by setting their `updated_at` to now,
+    /// without rescanning. Names not in the database are skipped. Returns the
+    /// number of entries touched.
+    pub fn touch(&mut self, names: &[String]) -> usize {
+        let now = Utc::now();
+        let names: HashSet<&String> = names.iter().collect();
+        
+        let mut touched = 0;
+        for name in names {
+            match self.db.entries.get_mut(name) {
+                Some(entry) => {
+                    entry.updated_at = now;
+                    touched += 1;
+                }
+                None => debug!("Cannot touch {}: class not found", name),
+            }
+        }
+        
+        if touched > 0 {
+            self.db.updated_at = now;
+        }
+        touched
+    }
+    
+    /// Save the database as one file per top-level config section (such as
+    /// `CfgVehicles`) plus an index file, into `dir`. Classes outside any
+    /// container go to a shared root shard.
+    pub fn save_sharded(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        file_utils::ensure_dir_exists(dir)?;
+        
+        let mut sections: BTreeMap<&str, BTreeMap<&String, &ClassEntry>> = BTreeMap::new();
+        for (name, entry) in &self.db.entries {
+            let section = entry.class.container_path.first().map_or(ROOT_SHARD, |s| s.as_str());
+            sections.entry(section).or_default().insert(name, entry);
+        }
+        
+        let mut shards = BTreeMap::new();
+        let mut used_names = HashSet::new();
+        for (section, entries) in &sections {
+            // Root classes get their own prefix so they can't clash with a section named "root"
+            let file_name = if *section == ROOT_SHARD {
+                format!("{}.json", ROOT_SHARD)
+            } else {
+                shard_file_name(section)
+            };
+            if !used_names.insert(file_name.to_lowercase()) {
+                bail!("Shard file name {} for section {} is already in use", file_name, section);
+            }
+            
+            let content = serde_json::to_string_pretty(entries)
+                .with_context(|| format!("Failed to serialize shard {}", section))?;
+            file_utils::write_string_to_file_atomic(dir.join(&file_name), &content)?;
+            shards.insert(section.to_string(), ShardRecord {
+                file: file_name,
+                checksum: hash_utils::hash_string(&content),
+            });
+        }
+        
+        let index = ShardIndex {
+            created_at: self.db.created_at,
+            updated_at: self.db.updated_at,
+            version: self.db.version.clone(),
+            shards,
+            file_classes: self.db.file_classes.clone().into_iter().collect(),
+            file_roots: self.db.file_roots.clone().into_iter().collect(),
+        };
+        let content = serde_json::to_string_pretty(&index)
+            .context("Failed to serialize shard index")?;
+        file_utils::write_string_to_file_atomic(dir.join(SHARD_INDEX_FILE), &content)?;
+        
+        info!("Saved {} classes in {} shards to {}", self.db.entries.len(), sections.len(), dir.display());
+        Ok(())
+    }
+    
+    /// Replace the loaded database with one reassembled from a directory
+    /// written by `save_sharded`. Fails if any shard doesn't match the
+    /// checksum recorded in the index.
+    pub fn load_sharded(&mut self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        let index_path = dir.join(SHARD_INDEX_FILE);
+        let content = file_utils::read_file_to_string(&index_path)?;
+        let index: ShardIndex = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse shard index {}", index_path.display()))?;
+        
+        let mut entries = HashMap::new();
+        for (section, record) in &index.shards {
+            let shard_path = dir.join(&record.file);
+            let content = file_utils::read_file_to_string(&shard_path)?;
+            let actual = hash_utils::hash_string(&content);
+            if actual != record.checksum {
+                bail!("Shard {} ({}) is corrupted: checksum mismatch (expected {}, found {})",
+                    section, shard_path.display(), record.checksum, actual);
+            }
+            let shard: HashMap<String, ClassEntry> = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse shard {} ({})", section, shard_path.display()))?;
+            debug!("Loaded {} classes from shard {}", shard.len(), section);
+            entries.extend(shard);
+        }
+        
+        self.db = ClassDatabase {
+            entries,
+            file_classes: index.file_classes.into_iter().collect(),
+            file_roots: index.file_roots.into_iter().collect(),
+            created_at: index.created_at,
+            updated_at: index.updated_at,
+            version: index.version,
+            checksum: None,
+        };
+        
+        info!("Loaded {} classes from {} shards in {}", self.db.entries.len(), index.shards.len(), dir.display());
+        Ok(())
+    }
+    
+    /// Write the entries updated at or after `since` as a JSON `DatabaseDelta`,
+    /// returning the number of entries written
+    pub fn export_delta(&self, since: DateTime<Utc>, writer: impl Write) -> Result<usize> {
+        let delta = DatabaseDelta {
+            since,
+            generated_at: Utc::now(),
+            entries: self.db.entries.iter()
+                .filter(|(_, entry)| entry.updated_at >= since)
+                .map(|(name, entry)| (name.clone(), entry.clone()))
+                .collect(),
+        };
+        
+        serde_json::to_writer_pretty(writer, &delta)
+            .context("Failed to write database delta")?;
+        
+        debug!("Exported {} changed entries since {}", delta.entries.len(), since);
+        Ok(delta.entries.len())
+    }
+    
+    /// Write the database to a fresh SQLite file for querying with external
+    /// SQL tools, replacing any existing file at `path`. Classes go in a
+    /// `classes` table, with container paths joined by `/`, and their
+    /// properties in a `properties` table in declaration order.
+    #[cfg(feature = "sqlite")]
+    pub fn export_sqlite(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to replace existing file {}", path.display()))?;
+        }
+        if let Some(parent) = path.parent() {
+            file_utils::ensure_dir_exists(parent)?;
+        }
+        
+        let mut conn = rusqlite::Connection::open(path)
+            .with_context(|| format!("Failed to create SQLite file {}", path.display()))?;
+        let txn = conn.transaction()?;
+        txn.execute_batch(
+            "CREATE TABLE classes (
+                name TEXT PRIMARY KEY,
+                parent TEXT,
+                container_path TEXT NOT NULL,
+                file_path TEXT,
+                is_forward_declaration INTEGER NOT NULL,
+                file_hash TEXT NOT NULL,
+                added_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE properties (
+                class_name TEXT NOT NULL REFERENCES classes(name),
+                position INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (class_name, position)
+            );
+            CREATE INDEX properties_by_name ON properties(name);"
+        ).context("Failed to create SQLite tables")?;
+        
+        {
+            let mut insert_class = txn.prepare(
+                "INSERT INTO classes VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+            )?;
+            let mut insert_property = txn.prepare(
+                "INSERT INTO properties VALUES (?1, ?2, ?3, ?4)"
+            )?;
+            
+            for entry in self.db.entries.values() {
+                let class = &entry.class;
+                insert_class.execute(rusqlite::params![
+                    class.name,
+                    class.parent,
+                    class.container_path.join("/"),
+                    class.file_path.as_ref().map(file_utils::path_key),
+                    class.is_forward_declaration,
+                    entry.file_hash,
+                    entry.added_at.to_rfc3339(),
+                    entry.updated_at.to_rfc3339(),
+                ])?;
+                
+                for (position, (name, value)) in class.properties.iter().enumerate() {
+                    insert_property.execute(rusqlite::params![class.name, position, name, value.as_ref()])?;
+                }
+            }
+        }
+        
+        txn.commit().context("Failed to write SQLite export")?;
+        info!("Exported {} classes to SQLite file {}", self.db.entries.len(), path.display());
+        Ok(())
+    }
+    
+    /// Build the inheritance hierarchy as nested nodes, roots sorted by name.
+    ///
+    /// Classes without a parent are roots. Classes whose parent isn't in the
+    /// database hang under a synthetic `external` node named after it. Classes
+    /// caught in an inheritance cycle get the cycle broken at one member, which
+    /// becomes a root, so every class appears exactly once.
+    pub fn class_tree(&self) -> Vec<ClassTreeNode> {
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut roots: Vec<&str> = Vec::new();
+        let mut external: BTreeSet<&str> = BTreeSet::new();
+        for entry in self.db.entries.values() {
+            match entry.class.parent.as_deref() {
+                Some(parent) => {
+                    children.entry(parent).or_default().push(&entry.class.name);
+                    if !self.db.entries.contains_key(parent) {
+                        external.insert(parent);
+                    }
+                }
+                None => roots.push(&entry.class.name),
+            }
+        }
+        for names in children.values_mut() {
+            names.sort();
+        }
+        roots.sort();
+        
+        let mut visited = HashSet::new();
+        let mut tree: Vec<ClassTreeNode> = roots.iter()
+            .map(|name| self.tree_node(name, &children, &mut visited))
+            .collect();
+        tree.extend(external.iter().map(|parent| ClassTreeNode {
+            name: parent.to_string(),
+            external: true,
+            children: children[parent].iter()
+                .map(|name| self.tree_node(name, &children, &mut visited))
+                .collect(),
+        }));
+        
+        // Anything left over is reachable only through a cycle: walk up from it
+        // until a class repeats, and root the cycle there
+        let mut remaining: Vec<&str> = self.db.entries.keys()
+            .map(|name| name.as_str())
+            .filter(|name| !visited.contains(name))
+            .collect();
+        remaining.sort();
+        for name in remaining {
+            if visited.contains(name) {
+                continue;
+            }
+            let mut seen = HashSet::new();
+            let mut current = name;
+            while seen.insert(current) {
+                current = self.db.entries[current].class.parent.as_deref()
+                    .expect("Classes left over after the walk from roots have a parent");
+            }
+            warn!("Breaking inheritance cycle at class {} for the class tree", current);
+            tree.push(self.tree_node(current, &children, &mut visited));
+        }
+        
+        tree.sort_by(|a, b| a.name.cmp(&b.name));
+        tree
+    }
+    
+    /// Build the node for a class and its descendants, skipping classes
+    /// already placed elsewhere in the tree
+    fn tree_node<'a>(
+        &self,
+        name: &'a str,
+        children: &HashMap<&str, Vec<&'a str>>,
+        visited: &mut HashSet<&'a str>,
+    ) -> ClassTreeNode {
+        visited.insert(name);
+        let mut node = ClassTreeNode {
+            name: name.to_string(),
+            external: false,
+            children: Vec::new(),
+        };
+        for child in children.get(name).into_iter().flatten() {
+            if !visited.contains(child) {
+                node.children.push(self.tree_node(child, children, visited));
+            }
+        }
+        node
+    }
+    
+    /// Write the inheritance hierarchy from `class_tree` as a JSON array of
+    /// nested nodes
+    pub fn export_tree_json(&self, writer: impl Write) -> Result<()> {
+        let tree = self.class_tree();
+        serde_json::to_writer_pretty(writer, &tree)
+            .context("Failed to write class tree")?;
+        
+        debug!("Exported class tree with {} roots", tree.len());
+        Ok(())
+    }
+    
+    /// Write every entry as one JSON line, sorted by class name, returning the
+    /// number of lines written
+    pub fn export_jsonl(&self, mut writer: impl Write) -> Result<usize> {
+        let mut names: Vec<&String> = self.db.entries.keys().collect();
+        names.sort();
+        
+        for name in &names {
+            serde_json::to_writer(&mut writer, &self.db.entries[*name])
+                .with_context(|| format!("Failed to write entry {}", name))?;
+            writer.write_all(b"\n")?;
+        }
+        
+        Ok(names.len())
+    }
+    
+    /// Build a database from `ClassEntry` JSON lines, one line at a time. The
+    /// file index is rebuilt from each entry's file path. The database has no
+    /// file until one is set with `with_db_path`.
+    pub fn import_jsonl(reader: impl BufRead) -> Result<Self> {
+        let mut db = ClassDatabase::default();
+        
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line.with_context(|| format!("Failed to read line {}", line_number + 1))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            
+            let entry: ClassEntry = serde_json::from_str(&line)
+                .with_context(|| format!("Failed to parse entry on line {}", line_number + 1))?;
+            
+            if let Some(file_path) = &entry.class.file_path {
+                let class_names = db.file_classes.entry(file_utils::path_key(file_path)).or_default();
+                if !class_names.contains(&entry.class.name) {
+                    class_names.push(entry.class.name.clone());
+                }
+            }
+            db.entries.insert(entry.class.name.clone(), entry);
+        }
+        
+        info!("Imported {} classes", db.entries.len());
+        Ok(Self {
+            storage: DatabaseStorage::new(PathBuf::new()),
+            db,
+        })
+    }
+}
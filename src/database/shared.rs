@@ -0,0 +1,69 @@
+use std::path::Path;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use anyhow::Result;
+
+use crate::class::types::ClassScanResult;
+use super::operations::{DatabaseOperations, QueryOptions};
+use super::types::{ClassDatabaseStats, ClassEntry};
+
+/// Thread-safe handle to a database, shared by cloning.
+///
+/// Any number of readers can query at once, while updates take exclusive
+/// access. Locks are only held for the duration of each call, so callers
+/// can't deadlock by holding one across calls.
+#[derive(Debug, Clone)]
+pub struct SharedDatabase {
+    /// The wrapped database operations
+    inner: Arc<RwLock<DatabaseOperations>>,
+}
+
+impl SharedDatabase {
+    /// Share an already loaded database
+    pub fn new(db_ops: DatabaseOperations) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(db_ops)),
+        }
+    }
+    
+    /// Load the database at the given path and share it
+    pub fn open(db_path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(DatabaseOperations::new(db_path)?))
+    }
+    
+    /// Run a query, returning copies of the matching entries so no lock is
+    /// held once this returns
+    pub fn read_query(&self, options: &QueryOptions) -> Vec<ClassEntry> {
+        self.read(|db_ops| db_ops.query(options).into_iter().cloned().collect())
+    }
+    
+    /// Apply scan results under an exclusive lock
+    pub fn write_update(&self, scan_result: ClassScanResult) -> Result<ClassDatabaseStats> {
+        self.write(|db_ops| db_ops.update_with_scan_results(scan_result))
+    }
+    
+    /// Run a function with shared read access
+    pub fn read<T>(&self, f: impl FnOnce(&DatabaseOperations) -> T) -> T {
+        f(&self.read_lock())
+    }
+    
+    /// Run a function with exclusive write access
+    pub fn write<T>(&self, f: impl FnOnce(&mut DatabaseOperations) -> T) -> T {
+        f(&mut self.write_lock())
+    }
+    
+    /// Save the database to disk. Readers can continue while it is written.
+    pub fn save(&self) -> Result<()> {
+        self.read(|db_ops| db_ops.save())
+    }
+    
+    /// Acquire the read lock. A writer that panicked may have left the
+    /// database half updated, so a poisoned lock is treated as fatal.
+    fn read_lock(&self) -> RwLockReadGuard<'_, DatabaseOperations> {
+        self.inner.read().expect("Shared database lock poisoned by a panicked writer")
+    }
+    
+    /// Acquire the write lock, treating a poisoned lock as fatal
+    fn write_lock(&self) -> RwLockWriteGuard<'_, DatabaseOperations> {
+        self.inner.write().expect("Shared database lock poisoned by a panicked writer")
+    }
+}
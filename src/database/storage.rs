@@ -1,15 +1,28 @@
 use std::path::{Path, PathBuf};
-use anyhow::{Result, Context};
-use log::{info, debug};
+use anyhow::{Result, Context, bail};
+use log::{info, debug, warn, error};
+use serde::Serialize;
 
 use crate::utils::file_utils;
 use super::types::ClassDatabase;
 
+/// A database as written to disk, with the checksum of its contents
+#[derive(Serialize)]
+struct ChecksummedDatabase<'a> {
+    #[serde(flatten)]
+    db: &'a ClassDatabase,
+    checksum: String,
+}
+
 /// Database storage operations
 #[derive(Debug)]
 pub struct DatabaseStorage {
     /// Path to the database file
     db_path: PathBuf,
+    
+    /// Whether `load` falls back to the backup, or else a fresh database,
+    /// when the database file can't be read
+    recover_on_corruption: bool,
 }
 
 impl DatabaseStorage {
@@ -17,9 +30,18 @@ impl DatabaseStorage {
     pub fn new(db_path: impl AsRef<Path>) -> Self {
         Self {
             db_path: db_path.as_ref().to_path_buf(),
+            recover_on_corruption: false,
         }
     }
     
+    /// Set whether a corrupt or truncated database file is recovered from
+    /// instead of failing `load`. The backup file is tried first, and if that
+    /// can't be loaded either, a fresh database is returned.
+    pub fn with_recover_on_corruption(mut self, recover_on_corruption: bool) -> Self {
+        self.recover_on_corruption = recover_on_corruption;
+        self
+    }
+    
     /// Load the database from disk
     pub fn load(&self) -> Result<ClassDatabase> {
         let path = &self.db_path;
@@ -30,11 +52,48 @@ impl DatabaseStorage {
             return Ok(ClassDatabase::default());
         }
         
+        let err = match Self::load_file(path) {
+            Ok(db) => return Ok(db),
+            Err(e) if !self.recover_on_corruption => return Err(e),
+            Err(e) => e,
+        };
+        warn!("Failed to load database {}: {:#}", path.display(), err);
+        
+        let backup = self.backup_path();
+        if backup.exists() {
+            match Self::load_file(&backup) {
+                Ok(db) => {
+                    warn!("Recovered database from backup {}", backup.display());
+                    return Ok(db);
+                }
+                Err(e) => warn!("Failed to load backup {}: {:#}", backup.display(), e),
+            }
+        }
+        
+        error!("Database {} is unrecoverable, starting with an empty database; it will be overwritten on the next save",
+            path.display());
+        Ok(ClassDatabase::default())
+    }
+    
+    /// Parse and verify a database file
+    fn load_file(path: &Path) -> Result<ClassDatabase> {
         let content = file_utils::read_file_to_string(path)?;
         
         let db: ClassDatabase = serde_json::from_str(&content)
             .with_context(|| format!("Failed to parse database file {}", path.display()))?;
         
+        match &db.checksum {
+            Some(expected) => {
+                let actual = db.compute_checksum()
+                    .context("Failed to compute database checksum")?;
+                if *expected != actual {
+                    bail!("Database file {} is corrupted: checksum mismatch (expected {}, found {})",
+                        path.display(), expected, actual);
+                }
+            }
+            None => debug!("Database file {} has no checksum, skipping verification", path.display()),
+        }
+        
         info!("Loaded database with {} classes", db.entries.len());
         Ok(db)
     }
@@ -49,10 +108,12 @@ impl DatabaseStorage {
             file_utils::ensure_dir_exists(parent)?;
         }
         
-        let content = serde_json::to_string_pretty(db)
+        let checksum = db.compute_checksum()
+            .context("Failed to compute database checksum")?;
+        let content = serde_json::to_string_pretty(&ChecksummedDatabase { db, checksum })
             .context("Failed to serialize database")?;
         
-        file_utils::write_string_to_file(path, &content)?;
+        file_utils::write_string_to_file_atomic(path, &content)?;
         
         info!("Saved database with {} classes", db.entries.len());
         Ok(())
@@ -63,8 +124,21 @@ impl DatabaseStorage {
         self.db_path.exists()
     }
     
+    /// Get the size of the database file in bytes, if it exists
+    pub fn file_size(&self) -> Option<u64> {
+        std::fs::metadata(&self.db_path).ok().map(|m| m.len())
+    }
+    
     /// Get the path to the database file
     pub fn path(&self) -> &Path {
         &self.db_path
     }
+    
+    /// Get the path of the backup used when recovering from corruption: the
+    /// database path with `.bak` appended, e.g. `classes.json.bak`
+    pub fn backup_path(&self) -> PathBuf {
+        let mut backup = self.db_path.clone().into_os_string();
+        backup.push(".bak");
+        PathBuf::from(backup)
+    }
 } 
\ No newline at end of file
@@ -1,11 +1,14 @@
-use std::collections::HashMap;
-use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use serde::{Serialize, Serializer, Deserialize};
 use chrono::{DateTime, Utc};
 
 use crate::class::types::ProcessedClass;
+use crate::utils::file_utils;
+use crate::utils::hash_utils::{self, HashAlgorithm};
 
 /// Entry in the class database
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ClassEntry {
     /// The processed class data
     pub class: ProcessedClass,
@@ -18,17 +21,31 @@ pub struct ClassEntry {
     
     /// Hash of the file content when this class was processed
     pub file_hash: String,
+    
+    /// Algorithm that produced `file_hash`
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
 }
 
 /// Database for storing and querying processed classes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassDatabase {
     /// Map of class name to class entry
+    #[serde(serialize_with = "serialize_sorted")]
     pub entries: HashMap<String, ClassEntry>,
     
     /// Map of file path to list of class names in that file
+    #[serde(serialize_with = "serialize_sorted")]
     pub file_classes: HashMap<String, Vec<String>>,
     
+    /// Identifier of the scan root each relative file path was stored from,
+    /// so files from different roots sharing a relative path aren't mixed up.
+    /// Files scanned without a root map to an empty string; files missing
+    /// here, as in databases saved before roots were tracked, have an
+    /// unknown root.
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub file_roots: HashMap<String, String>,
+    
     /// When this database was created
     pub created_at: DateTime<Utc>,
     
@@ -37,6 +54,22 @@ pub struct ClassDatabase {
     
     /// Version of the database schema
     pub version: String,
+    
+    /// Checksum of the entries and file index as read from disk. It is
+    /// recomputed and written alongside the data by `DatabaseStorage::save`.
+    #[serde(default, skip_serializing)]
+    pub checksum: Option<String>,
+}
+
+/// Borrowed view of the checksummed parts of a database
+#[derive(Serialize)]
+struct ChecksumContent<'a> {
+    entries: BTreeMap<&'a String, &'a ClassEntry>,
+    file_classes: BTreeMap<&'a String, &'a Vec<String>>,
+    /// Left out when empty, so databases saved before roots were tracked
+    /// keep their checksum
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    file_roots: BTreeMap<&'a String, &'a String>,
 }
 
 /// Statistics about the class database
@@ -58,14 +91,258 @@ pub struct ClassDatabaseStats {
     pub removed_classes: usize,
 }
 
+/// Structural overview of the class hierarchy stored in the database
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ClassDatabaseDetailedStats {
+    /// Totals as reported by `get_stats`
+    pub totals: ClassDatabaseStats,
+    
+    /// Number of classes without a parent
+    pub root_classes: usize,
+    
+    /// Number of classes that no other class inherits from
+    pub leaf_classes: usize,
+    
+    /// Longest chain of parent links found in the database
+    pub max_depth: usize,
+    
+    /// Most inherited-from parent names with their direct child counts
+    pub top_parents: Vec<(String, usize)>,
+}
+
+/// Entries changed since a point in time, as written by `DatabaseOperations::export_delta`.
+///
+/// Removals aren't tracked by the database, so a delta only adds or replaces entries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatabaseDelta {
+    /// Entries updated at or after this time are included
+    pub since: DateTime<Utc>,
+    
+    /// When the delta was generated
+    pub generated_at: DateTime<Utc>,
+    
+    /// Changed entries by class name
+    pub entries: BTreeMap<String, ClassEntry>,
+}
+
+/// Size information about the database, for capacity planning
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageInfo {
+    /// Size of the database file on disk, if it has been saved
+    pub file_bytes: Option<u64>,
+    
+    /// Rough in-memory footprint, summing the lengths of all stored strings
+    pub estimated_memory_bytes: usize,
+}
+
+/// A self-consistency problem found by `ClassDatabase::validate`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ValidationIssue {
+    /// `file_classes` lists a class that has no entry
+    MissingEntry {
+        /// File key in `file_classes`
+        file: String,
+        /// Class name without an entry
+        class_name: String,
+    },
+    
+    /// An entry's file is not listed for it in `file_classes`
+    UnindexedFile {
+        /// Class whose file isn't indexed
+        class_name: String,
+        /// File path recorded on the class
+        file: String,
+    },
+    
+    /// An entry is stored under a key that differs from its class name
+    KeyMismatch {
+        /// Key in `entries`
+        key: String,
+        /// Name recorded on the class
+        class_name: String,
+    },
+    
+    /// An entry was updated before it was added
+    InvalidTimestamps {
+        /// Class with inconsistent timestamps
+        class_name: String,
+    },
+}
+
+/// Kind of value a property is expected to hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PropertyKind {
+    /// A number such as `2` or `-0.5`
+    Number,
+    /// Any non-array value, including a quoted `"2"`
+    String,
+    /// An array such as `{1, 2}`
+    Array,
+}
+
+impl PropertyKind {
+    /// Classify a captured property value
+    pub fn of(value: &str) -> Self {
+        let value = value.trim();
+        if value.starts_with('{') {
+            PropertyKind::Array
+        } else if value.parse::<f64>().is_ok() {
+            PropertyKind::Number
+        } else {
+            PropertyKind::String
+        }
+    }
+    
+    /// Classify a captured property value, treating it as a string when it
+    /// was quoted in the source (see `ProcessedClass::quoted_properties`)
+    pub fn of_captured(value: &str, quoted: bool) -> Self {
+        if quoted {
+            PropertyKind::String
+        } else {
+            PropertyKind::of(value)
+        }
+    }
+    
+    /// Whether a captured value satisfies this kind
+    pub fn accepts(self, value: &str) -> bool {
+        self.accepts_captured(value, false)
+    }
+    
+    /// Whether a captured value satisfies this kind, treating it as a string
+    /// when it was quoted in the source
+    pub fn accepts_captured(self, value: &str, quoted: bool) -> bool {
+        match self {
+            PropertyKind::String => PropertyKind::of_captured(value, quoted) != PropertyKind::Array,
+            kind => PropertyKind::of_captured(value, quoted) == kind,
+        }
+    }
+}
+
+/// A property whose value doesn't have the kind required by a schema, as
+/// reported by `DatabaseOperations::validate_against_schema`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// Class defining the property
+    pub class_name: String,
+    
+    /// Name of the property
+    pub property: String,
+    
+    /// Kind required by the schema
+    pub expected: PropertyKind,
+    
+    /// Kind of the captured value
+    pub found: PropertyKind,
+    
+    /// The captured value
+    pub value: String,
+}
+
+/// A class and the classes inheriting from it, as written by
+/// `DatabaseOperations::export_tree_json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassTreeNode {
+    /// Class name
+    pub name: String,
+    
+    /// Whether this is a synthetic node for a parent not defined in the database
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+    
+    /// Direct children, sorted by name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ClassTreeNode>,
+}
+
+impl ClassDatabase {
+    /// Calculate a checksum over the entries, file index and file roots,
+    /// independent of map iteration order
+    pub fn compute_checksum(&self) -> serde_json::Result<String> {
+        let content = serde_json::to_string(&ChecksumContent {
+            entries: self.entries.iter().collect(),
+            file_classes: self.file_classes.iter().collect(),
+            file_roots: self.file_roots.iter().collect(),
+        })?;
+        Ok(hash_utils::hash_string(&content))
+    }
+    
+    /// Check the database for internal inconsistencies, such as those
+    /// introduced by hand-editing the JSON file
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        
+        let mut files: Vec<_> = self.file_classes.iter().collect();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+        for (file, class_names) in files {
+            for class_name in class_names {
+                if !self.entries.contains_key(class_name) {
+                    issues.push(ValidationIssue::MissingEntry {
+                        file: file.clone(),
+                        class_name: class_name.clone(),
+                    });
+                }
+            }
+        }
+        
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, entry) in entries {
+            let class = &entry.class;
+            
+            if *key != class.name {
+                issues.push(ValidationIssue::KeyMismatch {
+                    key: key.clone(),
+                    class_name: class.name.clone(),
+                });
+            }
+            
+            if let Some(file_path) = &class.file_path {
+                let file = file_utils::path_key(file_path);
+                let indexed = self.file_classes.get(&file)
+                    .is_some_and(|names| names.contains(&class.name));
+                if !indexed {
+                    issues.push(ValidationIssue::UnindexedFile {
+                        class_name: class.name.clone(),
+                        file,
+                    });
+                }
+            }
+            
+            if entry.added_at > entry.updated_at {
+                issues.push(ValidationIssue::InvalidTimestamps {
+                    class_name: class.name.clone(),
+                });
+            }
+        }
+        
+        issues
+    }
+}
+
 impl Default for ClassDatabase {
     fn default() -> Self {
         Self {
             entries: HashMap::new(),
             file_classes: HashMap::new(),
+            file_roots: HashMap::new(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
             version: env!("CARGO_PKG_VERSION").to_string(),
+            checksum: None,
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Serialize a map with its keys in sorted order so saved files diff cleanly
+fn serialize_sorted<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Identify a scan root without storing its machine-specific path
+pub(crate) fn root_id(root: &Path) -> String {
+    HashAlgorithm::Xxh3.hash_string(&file_utils::path_key(root))
+}
@@ -1,16 +1,20 @@
 pub mod class;
 pub mod database;
 pub mod utils;
+pub mod workflow;
 
 // Re-export main types and functions for easier access
 pub use class::types::{ProcessedClass, ClassScanStats};
 pub use class::scanner::ClassScanner;
 pub use class::processor::ClassProcessor;
-pub use class::types::ClassScanOptions;
+pub use class::types::{ClassScanOptions, SourceKind};
 
-pub use database::types::{ClassDatabase, ClassDatabaseStats};
+pub use database::types::{ClassDatabase, ClassDatabaseStats, ClassDatabaseDetailedStats};
 pub use database::DatabaseOperations;
 pub use database::QueryOptions;
 
 // Re-export utility functions
 pub use utils::file_utils;
+
+// Re-export high-level workflows
+pub use workflow::scan_and_update;
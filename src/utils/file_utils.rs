@@ -1,7 +1,11 @@
 use std::path::{Path, PathBuf};
+use std::borrow::Cow;
 use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
 use anyhow::{Result, Context};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 
 /// Create a directory if it doesn't exist
 pub fn ensure_dir_exists(dir: impl AsRef<Path>) -> Result<()> {
@@ -17,10 +21,9 @@ pub fn ensure_dir_exists(dir: impl AsRef<Path>) -> Result<()> {
 /// Check if a file has a specific extension
 pub fn has_extension(path: impl AsRef<Path>, extension: &str) -> bool {
     let path = path.as_ref();
-    if let Some(ext) = path.extension() {
-        if let Some(ext_str) = ext.to_str() {
-            return ext_str.eq_ignore_ascii_case(extension);
-        }
+    if let Some(ext) = path.extension()
+        && let Some(ext_str) = ext.to_str() {
+        return ext_str.eq_ignore_ascii_case(extension);
     }
     false
 }
@@ -32,33 +35,153 @@ pub fn has_any_extension(path: impl AsRef<Path>, extensions: &[&str]) -> bool {
 
 /// Get all files in a directory with specific extensions
 pub fn get_files_with_extensions(dir: impl AsRef<Path>, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let (files, errors) = get_files_with_extensions_and_errors(dir, extensions)?;
+    
+    for (path, error) in &errors {
+        warn!("Skipped {} during collection: {}", path.display(), error);
+    }
+    
+    Ok(files)
+}
+
+/// Paths that couldn't be read, each with the reason
+pub type PathErrors = Vec<(PathBuf, String)>;
+
+/// Get all files in a directory with specific extensions, along with every path
+/// the walk couldn't read (such as a directory without permission) and why
+pub fn get_files_with_extensions_and_errors(
+    dir: impl AsRef<Path>,
+    extensions: &[&str],
+) -> Result<(Vec<PathBuf>, PathErrors)> {
     let dir = dir.as_ref();
     debug!("Collecting files from directory: {}", dir.display());
     
     let mut files = Vec::new();
+    let mut errors = Vec::new();
     
-    for entry in walkdir::WalkDir::new(dir)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
+    for entry in walkdir::WalkDir::new(dir).follow_links(true) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let path = e.path().unwrap_or(dir).to_path_buf();
+                let message = match e.io_error() {
+                    Some(io_error) => io_error.to_string(),
+                    None => e.to_string(),
+                };
+                errors.push((path, message));
+                continue;
+            }
+        };
+        
         let path = entry.path();
-        if has_any_extension(path, extensions) {
+        if entry.file_type().is_file() && has_any_extension(path, extensions) {
             trace!("Found file: {}", path.display());
             files.push(path.to_owned());
         }
     }
     
+    debug!("Collected {} files with extensions {:?} ({} paths unreadable)", files.len(), extensions, errors.len());
+    Ok((files, errors))
+}
+
+/// Get all files in a directory with specific extensions, walking the tree in
+/// parallel. The result is sorted so it doesn't depend on thread scheduling.
+pub fn get_files_with_extensions_parallel(dir: impl AsRef<Path>, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    debug!("Collecting files in parallel from directory: {}", dir.display());
+    
+    let files = std::sync::Mutex::new(Vec::new());
+    
+    ignore::WalkBuilder::new(dir)
+        .standard_filters(false)
+        .follow_links(true)
+        .build_parallel()
+        .run(|| {
+            let files = &files;
+            Box::new(move |entry| {
+                if let Ok(entry) = entry
+                    && entry.file_type().is_some_and(|t| t.is_file())
+                    && has_any_extension(entry.path(), extensions) {
+                    trace!("Found file: {}", entry.path().display());
+                    files.lock().unwrap().push(entry.into_path());
+                }
+                ignore::WalkState::Continue
+            })
+        });
+    
+    let mut files = files.into_inner().unwrap();
+    files.sort();
+    
     debug!("Collected {} files with extensions {:?}", files.len(), extensions);
     Ok(files)
 }
 
-/// Read a file to string with better error handling
+/// Byte order mark some editors write at the start of UTF-8 files
+pub const UTF8_BOM: char = '\u{FEFF}';
+
+/// Read a file to string with better error handling, dropping a leading UTF-8 BOM
 pub fn read_file_to_string(path: impl AsRef<Path>) -> Result<String> {
     let path = path.as_ref();
-    fs::read_to_string(path)
-        .with_context(|| format!("Failed to read file {}", path.display()))
+    let mut content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file {}", path.display()))?;
+    
+    // Editors on Windows may prefix UTF-8 files with a byte order mark
+    if content.starts_with(UTF8_BOM) {
+        content.drain(..UTF8_BOM.len_utf8());
+    }
+    Ok(content)
+}
+
+/// Convert a path to the string used to key files in the database, with `/`
+/// separators so the same file gets the same key on Windows and Unix
+pub fn path_key(path: impl AsRef<Path>) -> String {
+    path.as_ref().to_string_lossy().replace('\\', "/")
+}
+
+/// Convert CRLF line endings to LF, borrowing when there is nothing to change
+pub fn normalize_line_endings(content: &str) -> Cow<'_, str> {
+    if content.contains("\r\n") {
+        Cow::Owned(content.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
+/// Delay before the first retry of a failed read; doubled on each attempt
+const READ_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Read a file to string, retrying transient failures up to `retries` times
+pub fn read_file_to_string_with_retry(path: impl AsRef<Path>, retries: u32) -> Result<String> {
+    let path = path.as_ref();
+    retry_with_backoff(retries, READ_RETRY_BACKOFF, || read_file_to_string(path))
+}
+
+/// Run `operation`, retrying with exponential backoff while it fails with a
+/// transient error. Missing files and invalid UTF-8 are never retried.
+pub fn retry_with_backoff<T>(retries: u32, backoff: Duration, mut operation: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut delay = backoff;
+    let mut attempt = 0;
+    
+    loop {
+        match operation() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < retries && is_transient(&e) => {
+                attempt += 1;
+                warn!("Attempt {} of {} failed, retrying in {:?}: {}", attempt, retries + 1, delay, e);
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Whether an error might succeed if the operation is retried
+fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<io::Error>() {
+        Some(io_error) => !matches!(io_error.kind(), io::ErrorKind::NotFound | io::ErrorKind::InvalidData),
+        None => true,
+    }
 }
 
 /// Write a string to a file with better error handling
@@ -72,4 +195,39 @@ pub fn write_string_to_file(path: impl AsRef<Path>, content: &str) -> Result<()>
     
     fs::write(path, content)
         .with_context(|| format!("Failed to write file {}", path.display()))
-} 
\ No newline at end of file
+}
+
+/// Append a single line to a file, creating the file if it doesn't exist
+pub fn append_line_to_file(path: impl AsRef<Path>, line: &str) -> Result<()> {
+    let path = path.as_ref();
+    
+    // Create parent directory if it doesn't exist
+    if let Some(parent) = path.parent() {
+        ensure_dir_exists(parent)?;
+    }
+    
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open file {} for appending", path.display()))?;
+    
+    writeln!(file, "{}", line)
+        .with_context(|| format!("Failed to append to file {}", path.display()))
+}
+
+/// Write a string to a file atomically by writing a sibling temporary file and
+/// renaming it into place, so readers never observe a partially written file
+pub fn write_string_to_file_atomic(path: impl AsRef<Path>, content: &str) -> Result<()> {
+    let path = path.as_ref();
+    
+    let mut tmp_name = path.file_name()
+        .with_context(|| format!("Invalid file path {}", path.display()))?
+        .to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    
+    write_string_to_file(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to move {} into place at {}", tmp_path.display(), path.display()))
+}
@@ -1,10 +1,40 @@
 use std::path::Path;
 use anyhow::Result;
 use sha2::{Sha256, Digest};
+use serde::{Serialize, Deserialize};
 use log::trace;
 
 use super::file_utils;
 
+/// Algorithm used to fingerprint file content for change detection
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    /// SHA-256, rendered as 64 hex digits
+    #[default]
+    Sha256,
+    /// 64-bit XXH3, a much faster non-cryptographic hash rendered as 16 hex digits
+    Xxh3,
+}
+
+impl HashAlgorithm {
+    /// Hash a string with this algorithm
+    pub fn hash_string(self, content: &str) -> String {
+        match self {
+            HashAlgorithm::Sha256 => hash_string(content),
+            HashAlgorithm::Xxh3 => format!("{:016x}", xxhash_rust::xxh3::xxh3_64(content.as_bytes())),
+        }
+    }
+    
+    /// Hash a file's content with this algorithm
+    pub fn hash_file(self, path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        trace!("Calculating {:?} hash for file: {}", self, path.display());
+        
+        let content = file_utils::read_file_to_string(path)?;
+        Ok(self.hash_string(&content))
+    }
+}
+
 /// Calculate SHA-256 hash of a string
 pub fn hash_string(content: &str) -> String {
     let mut hasher = Sha256::new();
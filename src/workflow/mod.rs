@@ -0,0 +1,8 @@
+mod scan_and_update;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+// Re-export from submodules
+pub use scan_and_update::scan_and_update;
+#[cfg(feature = "watch")]
+pub use watch::{DirectoryWatcher, WatchDelta, apply_file_change, watch_directory};
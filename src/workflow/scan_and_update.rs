@@ -0,0 +1,32 @@
+use std::path::Path;
+use anyhow::{Result, Context};
+use log::info;
+
+use crate::class::processor::ClassProcessor;
+use crate::class::types::ClassScanOptions;
+use crate::database::{ClassDatabaseStats, DatabaseOperations};
+
+/// Scan a directory, merge the results into the database at `db_path` and save it.
+///
+/// The database is created if it doesn't exist yet. Logs and temporary files
+/// go next to the database file.
+pub fn scan_and_update(
+    input_dir: impl AsRef<Path>,
+    db_path: impl AsRef<Path>,
+    options: ClassScanOptions,
+) -> Result<ClassDatabaseStats> {
+    let input_dir = input_dir.as_ref();
+    let db_path = db_path.as_ref();
+    info!("Scanning {} into database {}", input_dir.display(), db_path.display());
+    
+    let output_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let mut processor = ClassProcessor::new(options, output_dir);
+    let scan_result = processor.scan_directory(input_dir)
+        .with_context(|| format!("Failed to scan directory {}", input_dir.display()))?;
+    
+    let mut db_ops = DatabaseOperations::new(db_path)?;
+    let stats = db_ops.update_with_scan_results(scan_result)?;
+    db_ops.save()?;
+    
+    Ok(stats)
+}
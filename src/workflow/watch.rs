@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Utc};
+use log::{info, warn, debug};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use notify::event::ModifyKind;
+
+use crate::class::processor::ClassProcessor;
+use crate::class::scanner::FileCollector;
+use crate::database::DatabaseOperations;
+use crate::utils::file_utils;
+
+/// Changes applied to the database after one file changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WatchDelta {
+    /// The file that changed
+    pub file: PathBuf,
+    
+    /// Classes added to the database, sorted
+    pub added: Vec<String>,
+    
+    /// Classes whose stored entry was replaced, sorted
+    pub updated: Vec<String>,
+    
+    /// Classes removed from the database, sorted
+    pub removed: Vec<String>,
+}
+
+impl WatchDelta {
+    /// Whether the change left the database untouched
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Keeps a directory watch alive; watching stops when this is dropped
+pub struct DirectoryWatcher {
+    /// The underlying notify watcher
+    _watcher: RecommendedWatcher,
+}
+
+/// Rescan a single created, modified or deleted file and apply the result to
+/// the database
+pub fn apply_file_change(
+    db_ops: &mut DatabaseOperations,
+    processor: &mut ClassProcessor,
+    file: &Path,
+) -> Result<WatchDelta> {
+    let mut delta = WatchDelta {
+        file: file.to_path_buf(),
+        ..Default::default()
+    };
+    
+    // Classes are stored under the processor's path for the file, which is
+    // relative when it has a `path_root`
+    let stored = processor.stored_path(file);
+    if !file.exists() {
+        delta.removed = db_ops.remove_classes_in_file(&stored);
+        return Ok(delta);
+    }
+    
+    let file_key = file_utils::path_key(&stored);
+    let previous: Vec<String> = db_ops.database().file_classes.get(&file_key).cloned().unwrap_or_default();
+    
+    let result = processor.process_files(&[file.to_path_buf()])?;
+    if result.stats.error_files > 0 {
+        // Keep the stored classes rather than dropping them over a failed read
+        bail!("Failed to rescan {}", file.display());
+    }
+    
+    let before: HashMap<String, Option<DateTime<Utc>>> = result.classes.iter()
+        .map(|class| (class.name.clone(), db_ops.get_class(&class.name).map(|entry| entry.updated_at)))
+        .collect();
+    db_ops.update_with_scan_results(result)?;
+    
+    for (name, updated_at) in &before {
+        match (updated_at, db_ops.get_class(name)) {
+            (None, Some(_)) => delta.added.push(name.clone()),
+            (Some(previous), Some(entry)) if entry.updated_at != *previous => delta.updated.push(name.clone()),
+            _ => {}
+        }
+    }
+    
+    // Classes that were in the file before but aren't anymore
+    let current: HashSet<&String> = before.keys().collect();
+    for name in previous.iter().filter(|name| !current.contains(name)) {
+        let still_here = db_ops.get_class(name)
+            .is_some_and(|entry| entry.class.file_path.as_deref().map(file_utils::path_key) == Some(file_key.clone()));
+        if still_here {
+            db_ops.database_mut().entries.remove(name);
+            delta.removed.push(name.clone());
+        }
+    }
+    
+    delta.added.sort();
+    delta.updated.sort();
+    delta.removed.sort();
+    Ok(delta)
+}
+
+/// Watch a directory recursively, applying every change to a class file to the
+/// shared database and passing the non-empty deltas to `on_change`.
+///
+/// Changes are applied in memory only; call `save` on the database to persist them.
+pub fn watch_directory<F>(
+    dir: impl AsRef<Path>,
+    db_ops: Arc<Mutex<DatabaseOperations>>,
+    mut processor: ClassProcessor,
+    mut on_change: F,
+) -> Result<DirectoryWatcher>
+where
+    F: FnMut(&WatchDelta) + Send + 'static,
+{
+    let dir = dir.as_ref();
+    let collector = FileCollector::new();
+    
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Watch error: {}", e);
+                return;
+            }
+        };
+        
+        let relevant = match event.kind {
+            EventKind::Create(_) | EventKind::Remove(_) => true,
+            EventKind::Modify(ModifyKind::Metadata(_)) => false,
+            EventKind::Modify(_) => true,
+            _ => false,
+        };
+        if !relevant {
+            return;
+        }
+        
+        for path in event.paths.iter().filter(|path| collector.accepts(path)) {
+            debug!("Change detected in {}", path.display());
+            let mut db_ops = db_ops.lock().unwrap();
+            match apply_file_change(&mut db_ops, &mut processor, path) {
+                Ok(delta) if !delta.is_empty() => on_change(&delta),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to apply change to {}: {}", path.display(), e),
+            }
+        }
+    }).context("Failed to create file watcher")?;
+    
+    watcher.watch(dir, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch directory {}", dir.display()))?;
+    
+    info!("Watching {} for changes", dir.display());
+    Ok(DirectoryWatcher { _watcher: watcher })
+}
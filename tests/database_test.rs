@@ -0,0 +1,1372 @@
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::collections::HashSet;
+    use std::fs;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use anyhow::Result;
+    use tempfile::tempdir;
+    
+    use code_scanner::class::processor::ClassProcessor;
+    use code_scanner::class::types::{ClassScanResult, ProcessedClass};
+    use code_scanner::utils::hash_utils::HashAlgorithm;
+    use code_scanner::database::{ClassTreeNode, DatabaseDelta, DatabaseOperations, DatabaseStorage, PropertyKind, QueryOptions, SchemaViolation, SharedDatabase, ValidationIssue};
+    
+    /// Build a scan result from (name, parent) pairs without touching the filesystem
+    fn scan_result(classes: &[(&str, Option<&str>)]) -> ClassScanResult {
+        ClassScanResult {
+            classes: classes.iter()
+                .map(|(name, parent)| ProcessedClass {
+                    name: name.to_string(),
+                    parent: parent.map(|p| p.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+    
+    /// Build a class carrying the given properties
+    fn class_with_properties(name: &str, parent: Option<&str>, properties: &[(&str, &str)]) -> ProcessedClass {
+        ProcessedClass {
+            name: name.to_string(),
+            parent: parent.map(|p| p.to_string()),
+            properties: properties.iter()
+                .map(|(k, v)| (k.to_string(), Arc::from(*v)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_stats_detailed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        // Vehicle -> Tank -> Tank_Heavy, Vehicle -> Car, Rifle_Base (external) -> Rifle
+        db_ops.update_with_scan_results(scan_result(&[
+            ("Vehicle", None),
+            ("Tank", Some("Vehicle")),
+            ("Car", Some("Vehicle")),
+            ("Tank_Heavy", Some("Tank")),
+            ("Rifle", Some("Rifle_Base")),
+        ]))?;
+        
+        let stats = db_ops.stats_detailed(2);
+        
+        assert_eq!(stats.totals.total_classes, 5);
+        assert_eq!(stats.root_classes, 1, "Only Vehicle has no parent");
+        assert_eq!(stats.leaf_classes, 3, "Car, Tank_Heavy and Rifle have no children");
+        assert_eq!(stats.max_depth, 2, "Tank_Heavy sits two links below Vehicle");
+        assert_eq!(stats.top_parents, vec![
+            ("Vehicle".to_string(), 2),
+            ("Rifle_Base".to_string(), 1),
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_save_is_byte_stable() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        let names: Vec<String> = (0..50).map(|i| format!("Class_{}", i)).collect();
+        db_ops.update_with_scan_results(scan_result(
+            &names.iter().map(|n| (n.as_str(), Some("Base"))).collect::<Vec<_>>(),
+        ))?;
+        db_ops.save()?;
+        let first = fs::read(temp_dir.path().join("db.json"))?;
+        
+        // Rebuild the maps in reverse insertion order; the output must not change
+        let mut reordered = db_ops.database().clone();
+        let mut entries: Vec<_> = db_ops.database().entries.clone().into_iter().collect();
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+        reordered.entries = entries.into_iter().collect();
+        let second_path = temp_dir.path().join("db2.json");
+        DatabaseStorage::new(&second_path).save(&reordered)?;
+        let second = fs::read(&second_path)?;
+        
+        assert_eq!(first, second, "Saving identical data should produce identical bytes");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_intern_strings_shares_values() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("db.json");
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        
+        let mut result = scan_result(&[("Rifle_A", None), ("Rifle_B", None), ("Pistol", None)]);
+        for class in result.classes.iter_mut() {
+            let model = if class.name == "Pistol" { "\\pistol.p3d" } else { "\\rifle.p3d" };
+            class.properties.push(("model".to_string(), Arc::from(model)));
+        }
+        db_ops.update_with_scan_results(result)?;
+        db_ops.save()?;
+        
+        // Values loaded from disk start out as separate allocations
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        let model = |db: &DatabaseOperations, name: &str| db.get_class(name).unwrap().class.properties[0].1.clone();
+        assert!(!Arc::ptr_eq(&model(&db_ops, "Rifle_A"), &model(&db_ops, "Rifle_B")));
+        
+        assert_eq!(db_ops.intern_strings(), 1, "One duplicate model value should be shared");
+        assert!(Arc::ptr_eq(&model(&db_ops, "Rifle_A"), &model(&db_ops, "Rifle_B")));
+        assert!(!Arc::ptr_eq(&model(&db_ops, "Rifle_A"), &model(&db_ops, "Pistol")));
+        assert_eq!(&*model(&db_ops, "Rifle_B"), "\\rifle.p3d");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_compact_removes_stale_references() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[("Rifle", None), ("Pistol", None)]))?;
+        
+        let db = db_ops.database_mut();
+        db.file_classes.insert("weapons.hpp".to_string(), vec!["Rifle".to_string(), "Deleted".to_string()]);
+        db.file_classes.insert("pistols.hpp".to_string(), vec!["Pistol".to_string()]);
+        db.file_classes.insert("empty.hpp".to_string(), Vec::new());
+        db.file_classes.insert("gone.hpp".to_string(), vec!["AlsoDeleted".to_string()]);
+        
+        // "Deleted", "AlsoDeleted", then the now-empty "gone.hpp" and "empty.hpp"
+        assert_eq!(db_ops.compact(), 4);
+        
+        let db = db_ops.database();
+        assert_eq!(db.file_classes.len(), 2);
+        assert_eq!(db.file_classes["weapons.hpp"], vec!["Rifle".to_string()]);
+        assert_eq!(db.file_classes["pistols.hpp"], vec!["Pistol".to_string()]);
+        assert_eq!(db.entries.len(), 2, "Entries should be untouched");
+        
+        assert_eq!(db_ops.compact(), 0, "Compacting twice should find nothing");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_query_required_properties() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Complete", None, &[("scope", "2"), ("displayName", "Complete"), ("model", "a.p3d")]),
+                class_with_properties("Complete_Child", Some("Complete"), &[("model", "b.p3d"), ("scope", "2"), ("displayName", "Child")]),
+                class_with_properties("NoModel", None, &[("scope", "2"), ("displayName", "No Model")]),
+                class_with_properties("Bare", None, &[]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let required = vec!["scope".to_string(), "displayName".to_string(), "model".to_string()];
+        let mut options = QueryOptions {
+            required_properties: required,
+            sort_by: Some("name".to_string()),
+            ..Default::default()
+        };
+        let names: Vec<&str> = db_ops.query(&options).iter().map(|e| e.class.name.as_str()).collect();
+        assert_eq!(names, vec!["Complete", "Complete_Child"]);
+        
+        // Combines with the other filters
+        options.parent = Some("Complete".to_string());
+        let names: Vec<&str> = db_ops.query(&options).iter().map(|e| e.class.name.as_str()).collect();
+        assert_eq!(names, vec!["Complete_Child"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_storage_info() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("db.json");
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![class_with_properties("Rifle", Some("Rifle_Base"), &[("model", "rifle.p3d")])],
+            ..Default::default()
+        })?;
+        
+        let info = db_ops.storage_info();
+        assert_eq!(info.file_bytes, None, "Nothing has been saved yet");
+        // key + name + parent + property + hash ("unknown")
+        assert_eq!(info.estimated_memory_bytes, 5 + 5 + 10 + 5 + 9 + 7);
+        
+        db_ops.save()?;
+        assert_eq!(db_ops.storage_info().file_bytes, Some(fs::metadata(&db_path)?.len()));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_rename_class_updates_references() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[
+            ("Rifle_Base", None),
+            ("Rifle_A", Some("Rifle_Base")),
+            ("Rifle_B", Some("Rifle_Base")),
+            ("Pistol", None),
+        ]))?;
+        db_ops.database_mut().file_classes.insert("weapons.hpp".to_string(), vec![
+            "Rifle_Base".to_string(),
+            "Rifle_A".to_string(),
+        ]);
+        
+        // Two child parents plus one file_classes entry
+        assert_eq!(db_ops.rename_class("Rifle_Base", "Weapon_Base")?, 3);
+        
+        assert!(db_ops.get_class("Rifle_Base").is_none());
+        assert_eq!(db_ops.get_class("Weapon_Base").unwrap().class.name, "Weapon_Base");
+        assert_eq!(db_ops.get_class("Rifle_A").unwrap().class.parent.as_deref(), Some("Weapon_Base"));
+        assert_eq!(db_ops.get_class("Rifle_B").unwrap().class.parent.as_deref(), Some("Weapon_Base"));
+        assert_eq!(db_ops.database().file_classes["weapons.hpp"][0], "Weapon_Base");
+        
+        // Renaming onto an existing class or renaming a missing class fails
+        assert!(db_ops.rename_class("Rifle_A", "Pistol").is_err());
+        assert!(db_ops.rename_class("Missing", "Anything").is_err());
+        assert!(db_ops.get_class("Rifle_A").is_some(), "Failed renames leave the database untouched");
+        
+        // Renaming to the same name is a no-op
+        let before = db_ops.snapshot();
+        assert_eq!(db_ops.rename_class("Pistol", "Pistol")?, 0);
+        assert_eq!(db_ops.database().entries, before.entries);
+        assert!(db_ops.rename_class("Missing", "Missing").is_err());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_get_classes_partitions_found_and_missing() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[("Rifle", None), ("Pistol", None), ("Launcher", None)]))?;
+        
+        let lookup: Vec<String> = ["Pistol", "Grenade", "Rifle", "Mine"].iter().map(|s| s.to_string()).collect();
+        let (found, missing) = db_ops.get_classes(&lookup);
+        
+        let found: Vec<&str> = found.iter().map(|e| e.class.name.as_str()).collect();
+        assert_eq!(found, vec!["Pistol", "Rifle"]);
+        assert_eq!(missing, vec!["Grenade".to_string(), "Mine".to_string()]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_query_order_is_stable_without_sort() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        let names: Vec<String> = (0..30).map(|i| format!("Class_{:02}", 29 - i)).collect();
+        db_ops.update_with_scan_results(scan_result(
+            &names.iter().map(|n| (n.as_str(), None)).collect::<Vec<_>>(),
+        ))?;
+        
+        let options = QueryOptions::default();
+        let first: Vec<String> = db_ops.query(&options).iter().map(|e| e.class.name.clone()).collect();
+        let second: Vec<String> = db_ops.query(&options).iter().map(|e| e.class.name.clone()).collect();
+        
+        assert_eq!(first, second);
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(first, sorted, "Unsorted queries should be ordered by name");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_all_parents() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[
+            ("Vehicle", None),
+            ("Tank", Some("Vehicle")),
+            ("Car", Some("Vehicle")),
+            ("Rifle", Some("Rifle_Base_F")),
+            ("Pistol", Some("Pistol_Base_F")),
+        ]))?;
+        
+        assert_eq!(db_ops.all_parents(), vec![
+            "Pistol_Base_F".to_string(),
+            "Rifle_Base_F".to_string(),
+            "Vehicle".to_string(),
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_validate_reports_inconsistencies() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        let mut result = scan_result(&[("Rifle", None), ("Pistol", None), ("Launcher", None)]);
+        for class in result.classes.iter_mut() {
+            class.file_path = Some(PathBuf::from("weapons.hpp"));
+        }
+        db_ops.update_with_scan_results(result)?;
+        assert!(db_ops.database().validate().is_empty(), "A freshly updated database is consistent");
+        
+        let db = db_ops.database_mut();
+        db.file_classes.get_mut("weapons.hpp").unwrap().retain(|n| n != "Pistol");
+        db.file_classes.get_mut("weapons.hpp").unwrap().push("Ghost".to_string());
+        let rifle = db.entries.get_mut("Rifle").unwrap();
+        rifle.added_at = rifle.updated_at + chrono::Duration::hours(1);
+        let launcher = db.entries.remove("Launcher").unwrap();
+        db.entries.insert("launcher_old".to_string(), launcher);
+        
+        let issues = db_ops.database().validate();
+        assert_eq!(issues, vec![
+            ValidationIssue::MissingEntry { file: "weapons.hpp".to_string(), class_name: "Launcher".to_string() },
+            ValidationIssue::MissingEntry { file: "weapons.hpp".to_string(), class_name: "Ghost".to_string() },
+            ValidationIssue::UnindexedFile { class_name: "Pistol".to_string(), file: "weapons.hpp".to_string() },
+            ValidationIssue::InvalidTimestamps { class_name: "Rifle".to_string() },
+            ValidationIssue::KeyMismatch { key: "launcher_old".to_string(), class_name: "Launcher".to_string() },
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_query_by_file_pattern() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        let mut result = scan_result(&[("Rifle", None), ("Pistol", None), ("Car", None), ("Scope", None)]);
+        let files = [
+            "/mods/my_mod/weapons/rifles.hpp",
+            "/mods/my_mod/weapons/pistols.hpp",
+            "/mods/my_mod/vehicles/cars.hpp",
+            "/mods/my_mod/weapons/attachments/scopes.hpp",
+        ];
+        for (class, file) in result.classes.iter_mut().zip(files) {
+            class.file_path = Some(PathBuf::from(file));
+        }
+        db_ops.update_with_scan_results(result)?;
+        
+        let query = |pattern: &str| -> Vec<String> {
+            db_ops.query(&QueryOptions {
+                file_pattern: Some(pattern.to_string()),
+                ..Default::default()
+            }).iter().map(|e| e.class.name.clone()).collect()
+        };
+        
+        assert_eq!(query("**/weapons/*.hpp"), vec!["Pistol", "Rifle"]);
+        assert_eq!(query("**/weapons/**/*.hpp"), vec!["Pistol", "Rifle", "Scope"]);
+        assert_eq!(query("**/vehicles/*"), vec!["Car"]);
+        assert!(query("[invalid").is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_query_excluding_file_pattern() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        let mut result = scan_result(&[("Rifle", None), ("Rifle_Test", None), ("Car", None), ("Car_Test", None), ("Loose", None)]);
+        let files = [
+            "/mods/my_mod/weapons/rifles.hpp",
+            "/mods/my_mod/test/weapons/rifles.hpp",
+            "/mods/my_mod/vehicles/cars.hpp",
+            "/mods/my_mod/test/vehicles/cars.hpp",
+        ];
+        for (class, file) in result.classes.iter_mut().zip(files) {
+            class.file_path = Some(PathBuf::from(file));
+        }
+        db_ops.update_with_scan_results(result)?;
+        
+        let query = |include: Option<&str>, exclude: &str| -> Vec<String> {
+            db_ops.query(&QueryOptions {
+                file_pattern: include.map(str::to_string),
+                exclude_file_pattern: Some(exclude.to_string()),
+                ..Default::default()
+            }).iter().map(|e| e.class.name.clone()).collect()
+        };
+        
+        // Classes without a file can't match the excluded pattern
+        assert_eq!(query(None, "**/test/**"), vec!["Car", "Loose", "Rifle"]);
+        assert_eq!(query(Some("**/weapons/*.hpp"), "**/test/**"), vec!["Rifle"]);
+        assert!(query(None, "[invalid").is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_checksum_detects_tampering() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("db.json");
+        
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        db_ops.update_with_scan_results(scan_result(&[("Base", None), ("Child", Some("Base"))]))?;
+        db_ops.save()?;
+        
+        let reloaded = DatabaseStorage::new(&db_path).load()?;
+        assert!(reloaded.checksum.is_some());
+        assert_eq!(reloaded.checksum, Some(reloaded.compute_checksum()?));
+        
+        let content = fs::read_to_string(&db_path)?;
+        let tampered = content.replace("\"parent\": \"Base\"", "\"parent\": \"Other\"");
+        assert_ne!(content, tampered);
+        fs::write(&db_path, tampered)?;
+        
+        let err = DatabaseStorage::new(&db_path).load().unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_checksum_covers_file_roots() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("db.json");
+        
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        let mut result = scan_result(&[("Ace_Main", None)]);
+        result.classes[0].file_path = Some(PathBuf::from("config.cpp"));
+        result.path_root = Some(temp_dir.path().join("ace"));
+        db_ops.update_with_scan_results(result)?;
+        db_ops.save()?;
+        
+        let root = db_ops.database().file_roots["config.cpp"].clone();
+        let content = fs::read_to_string(&db_path)?;
+        let tampered = content.replace(&root, "0000000000000000");
+        assert_ne!(content, tampered);
+        fs::write(&db_path, tampered)?;
+        
+        let err = DatabaseStorage::new(&db_path).load().unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_leaf_classes() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[
+            ("Vehicle", None),
+            ("Car", Some("Vehicle")),
+            ("Truck", Some("Vehicle")),
+            ("SportsCar", Some("Car")),
+            ("Crate", Some("External")),
+        ]))?;
+        
+        let leaves: Vec<&str> = db_ops.leaf_classes().iter()
+            .map(|entry| entry.class.name.as_str())
+            .collect();
+        assert_eq!(leaves, vec!["Crate", "SportsCar", "Truck"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_hash_algorithm_switch_is_not_a_change() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        let file = PathBuf::from("config.cpp");
+        
+        let scan = |algorithm: HashAlgorithm, parent: Option<&str>| {
+            let mut result = scan_result(&[("Car", parent)]);
+            result.classes[0].file_path = Some(file.clone());
+            result.file_hashes.insert(file.clone(), algorithm.hash_string(&format!("{:?}", parent)));
+            result.hash_algorithm = algorithm;
+            result
+        };
+        
+        db_ops.update_with_scan_results(scan(HashAlgorithm::Sha256, Some("Vehicle")))?;
+        
+        // Same class, new algorithm: the hash is re-recorded without counting an update
+        let stats = db_ops.update_with_scan_results(scan(HashAlgorithm::Xxh3, Some("Vehicle")))?;
+        assert_eq!(stats.updated_classes, 0);
+        let entry = db_ops.get_class("Car").unwrap();
+        assert_eq!(entry.hash_algorithm, HashAlgorithm::Xxh3);
+        assert_eq!(entry.file_hash.len(), 16);
+        
+        // A real change under a new algorithm is still detected
+        let stats = db_ops.update_with_scan_results(scan(HashAlgorithm::Sha256, Some("Truck")))?;
+        assert_eq!(stats.updated_classes, 1);
+        assert_eq!(db_ops.get_class("Car").unwrap().hash_algorithm, HashAlgorithm::Sha256);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_property_frequency() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Rifle", None, &[("scope", "2"), ("displayName", "Rifle"), ("mass", "40")]),
+                class_with_properties("Pistol", None, &[("scope", "2"), ("displayName", "Pistol")]),
+                class_with_properties("Base", None, &[("scope", "0"), ("author", "me")]),
+            ],
+            ..Default::default()
+        })?;
+        
+        assert_eq!(db_ops.property_frequency(3), vec![
+            ("scope".to_string(), 3),
+            ("displayName".to_string(), 2),
+            ("author".to_string(), 1),
+        ]);
+        assert_eq!(db_ops.property_frequency(10).len(), 4);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_sharded_save_and_load() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let shard_dir = temp_dir.path().join("shards");
+        
+        let nested = |name: &str, parent: Option<&str>, container: &[&str]| ProcessedClass {
+            name: name.to_string(),
+            parent: parent.map(|p| p.to_string()),
+            container_path: container.iter().map(|c| c.to_string()).collect(),
+            file_path: Some(PathBuf::from("config.cpp")),
+            ..Default::default()
+        };
+        
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                nested("CfgVehicles", None, &[]),
+                nested("Car", None, &["CfgVehicles"]),
+                nested("Turrets", None, &["CfgVehicles", "Car"]),
+                nested("CfgWeapons", None, &[]),
+                nested("Rifle", None, &["CfgWeapons"]),
+            ],
+            ..Default::default()
+        })?;
+        db_ops.save_sharded(&shard_dir)?;
+        
+        assert!(shard_dir.join("index.json").exists());
+        assert!(shard_dir.join("root.json").exists());
+        let mut shard_files: Vec<String> = fs::read_dir(&shard_dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<Result<_>>()?;
+        shard_files.sort();
+        assert_eq!(shard_files.len(), 4);
+        assert!(shard_files[2].starts_with("section_cfgvehicles_"));
+        assert!(shard_files[3].starts_with("section_cfgweapons_"));
+        
+        let mut reloaded = DatabaseOperations::new(temp_dir.path().join("other.json"))?;
+        reloaded.load_sharded(&shard_dir)?;
+        
+        assert_eq!(reloaded.database().entries, db_ops.database().entries);
+        assert_eq!(reloaded.database().file_classes, db_ops.database().file_classes);
+        assert_eq!(reloaded.database().created_at, db_ops.database().created_at);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_sharded_sections_differing_by_case() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let shard_dir = temp_dir.path().join("shards");
+        
+        let nested = |name: &str, container: &str| ProcessedClass {
+            name: name.to_string(),
+            container_path: vec![container.to_string()],
+            ..Default::default()
+        };
+        
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![nested("Car", "CfgVehicles"), nested("Truck", "cfgVehicles")],
+            ..Default::default()
+        })?;
+        db_ops.save_sharded(&shard_dir)?;
+        
+        // Each section gets a file whose name differs by more than case
+        let shard_files: HashSet<String> = fs::read_dir(&shard_dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_lowercase()))
+            .collect::<Result<_>>()?;
+        assert_eq!(shard_files.len(), 3, "Index plus one shard per section: {:?}", shard_files);
+        
+        let mut reloaded = DatabaseOperations::new(temp_dir.path().join("other.json"))?;
+        reloaded.load_sharded(&shard_dir)?;
+        assert_eq!(reloaded.database().entries, db_ops.database().entries);
+        
+        // A shard edited after saving fails its checksum
+        let shard = fs::read_dir(&shard_dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?
+            .into_iter()
+            .find(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("section_")))
+            .unwrap();
+        let content = fs::read_to_string(&shard)?;
+        fs::write(&shard, content.replace("Car", "Van").replace("Truck", "Lorry"))?;
+        let err = reloaded.load_sharded(&shard_dir).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_export_delta() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[("Old", None), ("Edited", None)]))?;
+        
+        // Backdate the first batch so the second one is clearly more recent
+        let since = chrono::Utc::now();
+        for entry in db_ops.database_mut().entries.values_mut() {
+            entry.added_at = since - chrono::Duration::hours(1);
+            entry.updated_at = entry.added_at;
+        }
+        db_ops.update_with_scan_results(scan_result(&[("New", None)]))?;
+        db_ops.database_mut().entries.get_mut("Edited").unwrap().updated_at = since;
+        
+        let mut buffer = Vec::new();
+        let written = db_ops.export_delta(since, &mut buffer)?;
+        assert_eq!(written, 2);
+        
+        let delta: DatabaseDelta = serde_json::from_slice(&buffer)?;
+        assert_eq!(delta.since, since);
+        assert_eq!(delta.entries.keys().collect::<Vec<_>>(), vec!["Edited", "New"]);
+        assert_eq!(&delta.entries["New"], db_ops.get_class("New").unwrap());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_classes_overriding_property() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Tank_Base", None, &[("armor", "500")]),
+                class_with_properties("Tank_Heavy", Some("Tank_Base"), &[("armor", "800"), ("mass", "60000")]),
+                class_with_properties("Tank_Light", Some("Tank_Base"), &[("mass", "20000")]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let names: Vec<&str> = db_ops.classes_overriding_property("armor").iter()
+            .map(|entry| entry.class.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Tank_Heavy"]);
+        assert!(db_ops.classes_overriding_property("maxSpeed").is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_snapshot_and_restore() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let snapshot_path = temp_dir.path().join("snapshot.json");
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[("Base", None)]))?;
+        
+        let snapshot = db_ops.snapshot();
+        db_ops.save_snapshot(&snapshot_path)?;
+        
+        db_ops.update_with_scan_results(scan_result(&[("Risky", Some("Base"))]))?;
+        assert!(db_ops.get_class("Risky").is_some());
+        
+        db_ops.restore(snapshot);
+        assert!(db_ops.get_class("Risky").is_none());
+        assert!(db_ops.get_class("Base").is_some());
+        
+        // Roll back again from disk
+        db_ops.update_with_scan_results(scan_result(&[("Risky", Some("Base"))]))?;
+        db_ops.load_snapshot(&snapshot_path)?;
+        assert_eq!(db_ops.database().entries.keys().collect::<Vec<_>>(), vec!["Base"]);
+        
+        assert!(db_ops.load_snapshot(temp_dir.path().join("missing.json")).is_err());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_counts_by_root() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        let mut result = scan_result(&[("A", None), ("B", None), ("C", None), ("D", None), ("E", None)]);
+        let files = ["mods/ace/addons/a.hpp", "mods/ace/b.hpp", "mods/cba/c.hpp", "other/d.hpp"];
+        for (class, file) in result.classes.iter_mut().zip(files) {
+            class.file_path = Some(PathBuf::from(file));
+        }
+        db_ops.update_with_scan_results(result)?;
+        
+        let roots = vec![PathBuf::from("mods/ace"), PathBuf::from("mods/cba"), PathBuf::from("mods/rhs")];
+        let counts = db_ops.counts_by_root(&roots);
+        
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[Path::new("mods/ace")], 2);
+        assert_eq!(counts[Path::new("mods/cba")], 1);
+        // "other/d.hpp" and the class without a file match no root
+        assert_eq!(counts[Path::new("")], 2);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_case_insensitive_lookup_keeps_originals() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[("Foo", None), ("foo", Some("Foo")), ("Bar", None)]))?;
+        
+        let index = db_ops.lowercase_index();
+        assert_eq!(index["foo"], vec!["Foo", "foo"]);
+        assert_eq!(index["bar"], vec!["Bar"]);
+        
+        assert_eq!(db_ops.case_collisions(), vec![vec!["Foo".to_string(), "foo".to_string()]]);
+        
+        let matches: Vec<&str> = db_ops.get_classes_ignore_case("FOO").iter()
+            .map(|entry| entry.class.name.as_str())
+            .collect();
+        assert_eq!(matches, vec!["Foo", "foo"]);
+        assert_eq!(db_ops.get_classes_ignore_case("bar")[0].class.name, "Bar");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_jsonl_round_trip() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        let mut result = scan_result(&[("Base", None), ("Child", Some("Base")), ("Loose", None)]);
+        result.classes[0].file_path = Some(PathBuf::from("a.hpp"));
+        result.classes[1].file_path = Some(PathBuf::from("a.hpp"));
+        result.classes[2].properties.push(("scope".to_string(), Arc::from("2")));
+        db_ops.update_with_scan_results(result)?;
+        
+        let mut buffer = Vec::new();
+        assert_eq!(db_ops.export_jsonl(&mut buffer)?, 3);
+        assert_eq!(String::from_utf8(buffer.clone())?.lines().count(), 3);
+        
+        let imported = DatabaseOperations::import_jsonl(buffer.as_slice())?;
+        assert_eq!(imported.database().entries, db_ops.database().entries);
+        
+        // The file index is rebuilt in name order rather than scan order
+        let mut expected_files = db_ops.database().file_classes.clone();
+        for names in expected_files.values_mut() {
+            names.sort();
+        }
+        assert_eq!(imported.database().file_classes, expected_files);
+        
+        // An imported database is only saved once it has a file
+        assert!(imported.save().is_err());
+        let imported_path = temp_dir.path().join("imported.json");
+        let imported = imported.with_db_path(&imported_path);
+        imported.save()?;
+        assert_eq!(DatabaseOperations::new(&imported_path)?.database().entries, db_ops.database().entries);
+        
+        assert!(DatabaseOperations::import_jsonl("{not json}\n".as_bytes()).is_err());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_query_offset_pagination() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[("E", None), ("B", None), ("D", None), ("A", None), ("C", None)]))?;
+        
+        let page = |offset: usize, limit: usize| -> Vec<String> {
+            let options = QueryOptions {
+                offset: Some(offset),
+                limit: Some(limit),
+                ..Default::default()
+            };
+            db_ops.query(&options).iter().map(|entry| entry.class.name.clone()).collect()
+        };
+        
+        assert_eq!(page(2, 2), vec!["C", "D"]);
+        assert_eq!(page(4, 2), vec!["E"]);
+        assert!(page(10, 2).is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_missing_property_report() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Rifle_Base", None, &[("scope", "0")]),
+                class_with_properties("Rifle_A", Some("Rifle_Base"), &[("scope", "2")]),
+                class_with_properties("Rifle_C", Some("Rifle_Base"), &[]),
+                class_with_properties("Rifle_B", Some("Rifle_Base"), &[("mass", "10")]),
+                class_with_properties("Pistol_A", Some("Pistol_Base"), &[]),
+                class_with_properties("Loose", None, &[]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let report = db_ops.missing_property_report("scope");
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[&Some("Rifle_Base".to_string())], vec!["Rifle_B", "Rifle_C"]);
+        assert_eq!(report[&Some("Pistol_Base".to_string())], vec!["Pistol_A"]);
+        assert_eq!(report[&None], vec!["Loose"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_rescan_resets_file_classes() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        let scan = |classes: &[(&str, &str)], scanned: &[&str]| {
+            let mut result = scan_result(&classes.iter().map(|(name, _)| (*name, None)).collect::<Vec<_>>());
+            for (class, (_, file)) in result.classes.iter_mut().zip(classes) {
+                class.file_path = Some(PathBuf::from(file));
+            }
+            for file in scanned {
+                result.file_hashes.insert(PathBuf::from(file), file.to_string());
+            }
+            result
+        };
+        
+        db_ops.update_with_scan_results(scan(&[("Mover", "a.hpp"), ("Stayer", "a.hpp")], &["a.hpp"]))?;
+        assert_eq!(db_ops.database().file_classes["a.hpp"], vec!["Mover", "Stayer"]);
+        
+        // Mover moves to b.hpp; both files are part of the new scan
+        db_ops.update_with_scan_results(scan(&[("Stayer", "a.hpp"), ("Mover", "b.hpp")], &["a.hpp", "b.hpp"]))?;
+        assert_eq!(db_ops.database().file_classes["a.hpp"], vec!["Stayer"]);
+        assert_eq!(db_ops.database().file_classes["b.hpp"], vec!["Mover"]);
+        assert_eq!(db_ops.get_classes_in_file("a.hpp").len(), 1);
+        
+        // A file that no longer has classes loses its list; unscanned files are untouched
+        db_ops.update_with_scan_results(scan(&[], &["a.hpp"]))?;
+        assert!(!db_ops.database().file_classes.contains_key("a.hpp"));
+        assert_eq!(db_ops.database().file_classes["b.hpp"], vec!["Mover"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_sort_by_property_value() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Heavy", None, &[("armor", "800")]),
+                class_with_properties("Light", None, &[("armor", "90")]),
+                class_with_properties("Medium", None, &[("armor", "250.5")]),
+                class_with_properties("Scripted", None, &[("armor", "ARMOR_MACRO")]),
+                class_with_properties("Unarmored", None, &[]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let sorted = |descending: bool| -> Vec<String> {
+            let options = QueryOptions {
+                sort_by: Some("prop:armor".to_string()),
+                descending,
+                ..Default::default()
+            };
+            db_ops.query(&options).iter().map(|entry| entry.class.name.clone()).collect()
+        };
+        
+        // Numeric, not lexical: 90 < 250.5 < 800
+        assert_eq!(sorted(false), vec!["Light", "Medium", "Heavy", "Scripted", "Unarmored"]);
+        assert_eq!(sorted(true), vec!["Heavy", "Medium", "Light", "Scripted", "Unarmored"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_update_reports_progress() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        let mut calls = Vec::new();
+        let result = scan_result(&[("Base", None), ("Car", Some("Base")), ("Truck", Some("Car"))]);
+        db_ops.update_with_scan_results_with_progress(result, |processed, total| calls.push((processed, total)))?;
+        
+        assert_eq!(calls.last(), Some(&(3, 3)));
+        assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0), "Progress should only move forward");
+        assert!(calls.iter().all(|&(_, total)| total == 3));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_transaction_rolls_back_on_error() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("db.json");
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        db_ops.update_with_scan_results(scan_result(&[("Base", None), ("Car", Some("Base"))]))?;
+        db_ops.save()?;
+        let saved = std::fs::read_to_string(&db_path)?;
+        let before = db_ops.snapshot();
+        
+        let result: Result<()> = db_ops.transaction(|txn| {
+            txn.update_with_scan_results(scan_result(&[("Truck", Some("Car"))]))?;
+            txn.remove_classes_in_file("/nonexistent.hpp");
+            txn.rename_class("Car", "Car_F")?;
+            anyhow::bail!("interrupted");
+        });
+        
+        assert!(result.is_err());
+        assert_eq!(db_ops.database().entries, before.entries);
+        assert_eq!(db_ops.database().file_classes, before.file_classes);
+        assert_eq!(std::fs::read_to_string(&db_path)?, saved, "Nothing should be written");
+        
+        let added = db_ops.transaction(|txn| {
+            let stats = txn.update_with_scan_results(scan_result(&[("Truck", Some("Car"))]))?;
+            Ok(stats.added_classes)
+        })?;
+        assert_eq!(added, 1);
+        assert!(DatabaseOperations::new(&db_path)?.get_class("Truck").is_some(), "Committed changes should be saved");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_validate_against_schema() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Rifle", None, &[("scope", "2"), ("displayName", "Rifle"), ("magazines", "{\"30Rnd\"}")]),
+                class_with_properties("Pistol", None, &[("scope", "public"), ("displayName", "9"), ("magazines", "16Rnd")]),
+                class_with_properties("Bare", None, &[("author", "me")]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let schema = [
+            ("scope".to_string(), PropertyKind::Number),
+            ("displayName".to_string(), PropertyKind::String),
+            ("magazines".to_string(), PropertyKind::Array),
+        ].into_iter().collect();
+        let violations = db_ops.validate_against_schema(&schema);
+        
+        // A numeric-looking display name is still a valid string
+        assert_eq!(violations, vec![
+            SchemaViolation {
+                class_name: "Pistol".to_string(),
+                property: "scope".to_string(),
+                expected: PropertyKind::Number,
+                found: PropertyKind::String,
+                value: "public".to_string(),
+            },
+            SchemaViolation {
+                class_name: "Pistol".to_string(),
+                property: "magazines".to_string(),
+                expected: PropertyKind::Array,
+                found: PropertyKind::String,
+                value: "16Rnd".to_string(),
+            },
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_validate_quoted_number_against_schema() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class Quoted { scope = \"1\"; };\nclass Bare { scope = 1; };\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(processor.scan_specific_files(&[file])?)?;
+        
+        let schema = [("scope".to_string(), PropertyKind::Number)].into_iter().collect();
+        assert_eq!(db_ops.validate_against_schema(&schema), vec![
+            SchemaViolation {
+                class_name: "Quoted".to_string(),
+                property: "scope".to_string(),
+                expected: PropertyKind::Number,
+                found: PropertyKind::String,
+                value: "1".to_string(),
+            },
+        ]);
+        
+        let schema = [("scope".to_string(), PropertyKind::String)].into_iter().collect();
+        assert!(db_ops.validate_against_schema(&schema).is_empty(), "Numbers are valid strings");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_files_defining() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let rifles = temp_dir.path().join("rifles.hpp");
+        let carbines = temp_dir.path().join("carbines.hpp");
+        fs::write(&rifles, "class Rifle_Base {};\nclass Rifle: Rifle_Base {};\n")?;
+        fs::write(&carbines, "class Rifle_Base {};\nclass Carbine: Rifle_Base {};\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(processor.scan_specific_files(&[rifles.clone(), carbines.clone()])?)?;
+        
+        assert_eq!(db_ops.files_defining("Rifle"), vec![rifles.clone()]);
+        assert_eq!(db_ops.files_defining("Carbine"), vec![carbines.clone()]);
+        let mut expected = vec![rifles, carbines];
+        expected.sort();
+        assert_eq!(db_ops.files_defining("Rifle_Base"), expected);
+        assert!(db_ops.files_defining("Missing").is_empty());
+        
+        Ok(())
+    }
+    
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_export_sqlite() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Rifle_Base", None, &[("scope", "0")]),
+                class_with_properties("Rifle", Some("Rifle_Base"), &[("scope", "2"), ("displayName", "Rifle")]),
+                class_with_properties("Pistol", None, &[]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let sqlite_path = temp_dir.path().join("export").join("classes.sqlite");
+        db_ops.export_sqlite(&sqlite_path)?;
+        // Exporting again replaces the file rather than failing on existing tables
+        db_ops.export_sqlite(&sqlite_path)?;
+        
+        let conn = rusqlite::Connection::open(&sqlite_path)?;
+        let count: usize = conn.query_row("SELECT COUNT(*) FROM classes", [], |row| row.get(0))?;
+        assert_eq!(count, 3);
+        
+        let parent: Option<String> = conn.query_row("SELECT parent FROM classes WHERE name = 'Rifle'", [], |row| row.get(0))?;
+        assert_eq!(parent.as_deref(), Some("Rifle_Base"));
+        
+        let scoped: Vec<(String, String)> = conn.prepare("SELECT class_name, value FROM properties WHERE name = 'scope' ORDER BY class_name")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        assert_eq!(scoped, vec![("Rifle".to_string(), "2".to_string()), ("Rifle_Base".to_string(), "0".to_string())]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_recover_on_corruption() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("classes.json");
+        
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        db_ops.update_with_scan_results(scan_result(&[("Base", None), ("Car", Some("Base"))]))?;
+        db_ops.save()?;
+        
+        // Keep a good copy as the backup, then truncate the main file mid-write
+        let storage = DatabaseStorage::new(&db_path);
+        assert_eq!(storage.backup_path(), temp_dir.path().join("classes.json.bak"));
+        fs::copy(&db_path, storage.backup_path())?;
+        let content = fs::read_to_string(&db_path)?;
+        fs::write(&db_path, &content[..content.len() / 2])?;
+        
+        assert!(DatabaseOperations::new(&db_path).is_err(), "Corruption is an error unless recovery is enabled");
+        
+        let recovered = DatabaseOperations::from_storage(storage.with_recover_on_corruption(true))?;
+        assert!(recovered.get_class("Car").is_some(), "The backup should be loaded");
+        
+        // Without a usable backup, a fresh database is returned
+        fs::write(temp_dir.path().join("classes.json.bak"), "{")?;
+        let fresh = DatabaseStorage::new(&db_path).with_recover_on_corruption(true).load()?;
+        assert!(fresh.entries.is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_effective_properties() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Rifle_Base", None, &[("scope", "1"), ("model", "base.p3d"), ("mass", "40")]),
+                class_with_properties("Rifle", Some("Rifle_Base"), &[("scope", "2"), ("displayName", "Rifle")]),
+                class_with_properties("Rifle_Long", Some("Rifle"), &[("mass", "60")]),
+                class_with_properties("Loop_A", Some("Loop_B"), &[("a", "1")]),
+                class_with_properties("Loop_B", Some("Loop_A"), &[("a", "2"), ("b", "2")]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let effective = |name: &str| -> Vec<(String, String)> { db_ops.effective_properties(name) };
+        let expected = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+        };
+        
+        assert_eq!(effective("Rifle"), expected(&[("scope", "2"), ("model", "base.p3d"), ("mass", "40"), ("displayName", "Rifle")]));
+        assert_eq!(effective("Rifle_Long"), expected(&[("scope", "2"), ("model", "base.p3d"), ("mass", "60"), ("displayName", "Rifle")]));
+        assert_eq!(effective("Loop_A"), expected(&[("a", "1"), ("b", "2")]), "Cycles should terminate");
+        assert!(effective("Missing").is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_query_definitions_only() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let declarations = temp_dir.path().join("declarations.hpp");
+        let definitions = temp_dir.path().join("definitions.hpp");
+        fs::write(&declarations, "class Rifle_Base;\nclass Pistol_Base;\n")?;
+        fs::write(&definitions, "class Rifle_Base {\n    scope = 0;\n};\nclass Rifle: Rifle_Base {};\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(processor.scan_specific_files(&[declarations, definitions])?)?;
+        
+        let query = |definitions_only: bool| -> Vec<String> {
+            db_ops.query(&QueryOptions {
+                definitions_only,
+                ..Default::default()
+            }).iter().map(|e| e.class.name.clone()).collect()
+        };
+        
+        assert_eq!(query(false), vec!["Pistol_Base", "Rifle", "Rifle_Base"]);
+        assert_eq!(query(true), vec!["Rifle", "Rifle_Base"]);
+        assert!(!db_ops.get_class("Rifle_Base").unwrap().class.is_forward_declaration);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_path_keys_use_forward_slashes() -> Result<()> {
+        use code_scanner::utils::file_utils;
+        
+        assert_eq!(file_utils::path_key(r"C:\mods\my_mod\config.cpp"), "C:/mods/my_mod/config.cpp");
+        assert_eq!(file_utils::path_key(r"C:\mods\my_mod\config.cpp"), file_utils::path_key("C:/mods/my_mod/config.cpp"));
+        
+        let temp_dir = tempdir()?;
+        let db_path = temp_dir.path().join("db.json");
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        let mut result = scan_result(&[("Rifle", None)]);
+        result.classes[0].file_path = Some(PathBuf::from(r"C:\mods\my_mod\weapons.hpp"));
+        db_ops.update_with_scan_results(result)?;
+        
+        let keys: Vec<&String> = db_ops.database().file_classes.keys().collect();
+        assert_eq!(keys, vec!["C:/mods/my_mod/weapons.hpp"]);
+        assert_eq!(db_ops.get_classes_in_file("C:/mods/my_mod/weapons.hpp").len(), 1);
+        assert_eq!(db_ops.get_classes_in_file(r"C:\mods\my_mod\weapons.hpp").len(), 1);
+        
+        // Rescanning the same file spelled with forward slashes updates the same key
+        let mut result = scan_result(&[("Rifle", None), ("Carbine", Some("Rifle"))]);
+        for class in &mut result.classes {
+            class.file_path = Some(PathBuf::from("C:/mods/my_mod/weapons.hpp"));
+        }
+        db_ops.update_with_scan_results(result)?;
+        assert_eq!(db_ops.database().file_classes.len(), 1);
+        
+        db_ops.save()?;
+        let saved = fs::read_to_string(&db_path)?;
+        assert!(!saved.contains(r"\\"), "Saved paths should use forward slashes");
+        let reloaded = DatabaseOperations::new(&db_path)?;
+        assert_eq!(reloaded.get_class("Carbine").unwrap().class.file_path, Some(PathBuf::from("C:/mods/my_mod/weapons.hpp")));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_recompute_stats() -> Result<()> {
+        use code_scanner::database::ClassEntry;
+        
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        // Build the database by hand, as if imported or edited outside the crate
+        let db = db_ops.database_mut();
+        for (name, file) in [("Base", Some("/mods/a.hpp")), ("Car", Some("/mods/a.hpp")), ("Truck", Some("/mods/b.hpp")), ("Loose", None)] {
+            let mut class = class_with_properties(name, None, &[]);
+            class.file_path = file.map(PathBuf::from);
+            db.entries.insert(name.to_string(), ClassEntry {
+                class,
+                added_at: db.created_at,
+                updated_at: db.created_at,
+                file_hash: "unknown".to_string(),
+                hash_algorithm: HashAlgorithm::default(),
+            });
+        }
+        db.file_classes.insert("/mods/a.hpp".to_string(), vec!["Base".to_string(), "Car".to_string()]);
+        db.file_classes.insert("/mods/removed.hpp".to_string(), vec!["Gone".to_string()]);
+        db.file_classes.insert("/mods/empty.hpp".to_string(), Vec::new());
+        
+        let stats = db_ops.recompute_stats();
+        assert_eq!(stats.total_classes, 4);
+        assert_eq!(stats.total_files, 2, "Only a.hpp and b.hpp hold stored classes");
+        assert_eq!((stats.added_classes, stats.updated_classes, stats.removed_classes), (0, 0, 0));
+        assert_eq!(db_ops.get_stats().total_files, 3);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_self_inheriting() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[
+            ("Foo", Some("Foo")),
+            ("Bar", Some("bar")),
+            ("Baz", Some("Foo")),
+            ("Root", None),
+        ]))?;
+        
+        let names: Vec<&str> = db_ops.self_inheriting().iter().map(|e| e.class.name.as_str()).collect();
+        assert_eq!(names, vec!["Bar", "Foo"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_query_grouped_by_parent() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(ClassScanResult {
+            classes: vec![
+                class_with_properties("Car", None, &[("scope", "2")]),
+                class_with_properties("Hatchback", Some("Car"), &[("scope", "2")]),
+                class_with_properties("Sedan", Some("Car"), &[("scope", "2")]),
+                class_with_properties("Truck_Heavy", Some("Truck"), &[("scope", "2")]),
+                class_with_properties("Car_Base", None, &[("scope", "0")]),
+            ],
+            ..Default::default()
+        })?;
+        
+        let groups = db_ops.query_grouped_by_parent(&QueryOptions {
+            property_name: Some("scope".to_string()),
+            property_value: Some("2".to_string()),
+            ..Default::default()
+        });
+        
+        let grouped: Vec<(Option<&str>, Vec<&str>)> = groups.iter()
+            .map(|(parent, entries)| (parent.as_deref(), entries.iter().map(|e| e.class.name.as_str()).collect()))
+            .collect();
+        assert_eq!(grouped, vec![
+            (None, vec!["Car"]),
+            (Some("Car"), vec!["Hatchback", "Sedan"]),
+            (Some("Truck"), vec!["Truck_Heavy"]),
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_shared_database_concurrent_readers_and_writer() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let shared = SharedDatabase::open(temp_dir.path().join("db.json"))?;
+        const WRITES: usize = 200;
+        
+        // Raised when the writer finishes, even by panicking, so readers never spin forever
+        struct DoneOnDrop(Arc<AtomicBool>);
+        impl Drop for DoneOnDrop {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+        let done = Arc::new(AtomicBool::new(false));
+        
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                let done = done.clone();
+                std::thread::spawn(move || {
+                    // Each read sees a consistent snapshot, and the writer only
+                    // ever adds classes, so the count never shrinks
+                    let mut last_seen = 0;
+                    loop {
+                        let finished = done.load(Ordering::SeqCst);
+                        let (count, total) = shared.read(|db_ops| {
+                            (db_ops.query(&QueryOptions::default()).len(), db_ops.get_stats().total_classes)
+                        });
+                        assert_eq!(count, total, "Stats and entries disagree under one lock");
+                        assert!(count >= last_seen, "Reader saw classes disappear");
+                        last_seen = count;
+                        if finished {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+        
+        let writer = {
+            let shared = shared.clone();
+            let done = DoneOnDrop(done.clone());
+            std::thread::spawn(move || -> Result<()> {
+                let _done = done;
+                for i in 0..WRITES {
+                    shared.write_update(scan_result(&[(&format!("Class_{}", i), None)]))?;
+                }
+                Ok(())
+            })
+        };
+        
+        writer.join().expect("Writer panicked")?;
+        for reader in readers {
+            reader.join().expect("Reader panicked");
+        }
+        
+        assert_eq!(shared.read_query(&QueryOptions::default()).len(), WRITES);
+        shared.save()?;
+        assert_eq!(DatabaseOperations::new(temp_dir.path().join("db.json"))?.get_stats().total_classes, WRITES);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_export_tree_json() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        
+        // Vehicle -> Car -> Car_Sport and Vehicle -> Tank, Rifle under the
+        // undefined Rifle_Base, and a Loop_A <-> Loop_B cycle
+        db_ops.update_with_scan_results(scan_result(&[
+            ("Vehicle", None),
+            ("Car", Some("Vehicle")),
+            ("Car_Sport", Some("Car")),
+            ("Tank", Some("Vehicle")),
+            ("Rifle", Some("Rifle_Base")),
+            ("Loop_A", Some("Loop_B")),
+            ("Loop_B", Some("Loop_A")),
+        ]))?;
+        
+        let mut buffer = Vec::new();
+        db_ops.export_tree_json(&mut buffer)?;
+        let tree: Vec<ClassTreeNode> = serde_json::from_slice(&buffer)?;
+        
+        let node = |name: &str, children: Vec<ClassTreeNode>| ClassTreeNode {
+            name: name.to_string(),
+            external: false,
+            children,
+        };
+        assert_eq!(tree, vec![
+            node("Loop_A", vec![node("Loop_B", vec![])]),
+            ClassTreeNode {
+                name: "Rifle_Base".to_string(),
+                external: true,
+                children: vec![node("Rifle", vec![])],
+            },
+            node("Vehicle", vec![
+                node("Car", vec![node("Car_Sport", vec![])]),
+                node("Tank", vec![]),
+            ]),
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_touch() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(scan_result(&[("Car", None), ("Truck", None), ("Tank", None)]))?;
+        
+        let before = |db_ops: &DatabaseOperations, name: &str| db_ops.get_class(name).unwrap().updated_at;
+        let car_before = before(&db_ops, "Car");
+        let tank_before = before(&db_ops, "Tank");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        
+        let touched = db_ops.touch(&["Car".to_string(), "Truck".to_string(), "Car".to_string(), "Missing".to_string()]);
+        
+        assert_eq!(touched, 2, "Duplicates and unknown names are not counted");
+        assert!(db_ops.get_class("Car").unwrap().updated_at > car_before);
+        assert_eq!(db_ops.get_class("Car").unwrap().updated_at, db_ops.get_class("Truck").unwrap().updated_at);
+        assert_eq!(db_ops.get_class("Tank").unwrap().updated_at, tank_before);
+        assert_eq!(db_ops.get_class("Car").unwrap().added_at, car_before, "Touching keeps the added time");
+        assert_eq!(db_ops.touch(&[]), 0);
+        
+        Ok(())
+    }
+}
@@ -1 +1,5 @@
-pub mod simple_parser_test;
\ No newline at end of file
+pub mod simple_parser_test;
+pub mod database_test;
+pub mod processor_test;
+pub mod workflow_test;
+pub mod utils_test;
\ No newline at end of file
@@ -0,0 +1,1040 @@
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::fs;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use anyhow::Result;
+    use tempfile::tempdir;
+    
+    use code_scanner::class::processor::ClassProcessor;
+    use code_scanner::class::scanner::{ClassBlock, ClassExtractor};
+    use code_scanner::class::scanner::simple_parser::SimpleParser;
+    use code_scanner::class::types::{ClassConflict, ClassScanOptions, ClassScanResult, ExtensionStats, FileBench, ScanAuditRecord, ScanMode};
+    use code_scanner::database::DatabaseOperations;
+    
+    #[test]
+    fn test_audit_log_appends_per_scan() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        let audit_path = temp_dir.path().join("logs").join("audit.jsonl");
+        
+        let class_file = temp_dir.path().join("weapons.hpp");
+        fs::write(&class_file, "class Rifle_Base;\nclass Rifle: Rifle_Base {};\n")?;
+        
+        let mut processor = ClassProcessor::new(ClassScanOptions::default(), &output_dir)
+            .with_audit_log(&audit_path);
+        
+        processor.scan_specific_files(std::slice::from_ref(&class_file))?;
+        processor.scan_specific_files(std::slice::from_ref(&class_file))?;
+        
+        let content = fs::read_to_string(&audit_path)?;
+        let records: Vec<ScanAuditRecord> = content.lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        
+        assert_eq!(records.len(), 2, "Each scan should append one audit line");
+        assert!(records.iter().all(|r| r.total_files == 1 && r.total_classes == 2));
+        
+        Ok(())
+    }
+    
+    /// Parses with `SimpleParser`, raising the cancel flag on the first file
+    #[derive(Debug)]
+    struct CancellingExtractor {
+        cancel: Arc<AtomicBool>,
+    }
+    
+    impl ClassExtractor for CancellingExtractor {
+        fn extract(&self, content: &str, path: &Path) -> Result<Vec<ClassBlock>> {
+            self.cancel.store(true, Ordering::Relaxed);
+            SimpleParser::new(false).extract(content, path)
+        }
+    }
+    
+    #[test]
+    fn test_cancelled_scan_returns_partial_results() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let files: Vec<PathBuf> = (0..500)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("file_{}.hpp", i));
+                fs::write(&path, format!("class Class_{};\n", i)).map(|_| path)
+            })
+            .collect::<Result<_, _>>()?;
+        
+        // The flag is raised while the first file is parsed, so files already
+        // underway finish but the rest are skipped
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut processor = ClassProcessor::new(ClassScanOptions::default(), &output_dir)
+            .with_parser(Box::new(CancellingExtractor { cancel: cancel.clone() }));
+        let result = processor.process_files_cancellable(&files, cancel.clone())?;
+        
+        assert!(result.cancelled, "Result should be flagged as cancelled");
+        assert!(result.stats.total_files > 0, "Files parsed before the flag was seen are kept");
+        assert!(result.stats.total_files < files.len(), "Fewer than all files should be processed");
+        assert_eq!(result.classes.len(), result.stats.total_files);
+        
+        // A flag raised during the last file doesn't mark a complete scan as cancelled
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut processor = ClassProcessor::new(ClassScanOptions::default(), &output_dir)
+            .with_parser(Box::new(CancellingExtractor { cancel: cancel.clone() }));
+        let result = processor.process_files_cancellable(&files[..1], cancel.clone())?;
+        assert!(cancel.load(Ordering::Relaxed));
+        assert!(!result.cancelled);
+        assert_eq!(result.stats.total_files, 1);
+        
+        // Without cancellation every file is processed
+        let mut processor = ClassProcessor::new(ClassScanOptions::default(), &output_dir);
+        let result = processor.process_files(&files)?;
+        assert!(!result.cancelled);
+        assert_eq!(result.stats.total_files, files.len());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_line_ending_normalization_stabilizes_hashes() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let lf_file = temp_dir.path().join("lf.hpp");
+        let crlf_file = temp_dir.path().join("crlf.hpp");
+        fs::write(&lf_file, "class Rifle_Base;\nclass Rifle: Rifle_Base {\n    scope = 2;\n};\n")?;
+        fs::write(&crlf_file, "class Rifle_Base;\r\nclass Rifle: Rifle_Base {\r\n    scope = 2;\r\n};\r\n")?;
+        let files = vec![lf_file.clone(), crlf_file.clone()];
+        
+        let options = ClassScanOptions {
+            normalize_line_endings: true,
+            ..ClassScanOptions::default()
+        };
+        let result = ClassProcessor::new(options.clone(), &output_dir).process_files(&files)?;
+        assert_eq!(result.file_hashes[&lf_file], result.file_hashes[&crlf_file],
+                   "Normalized hashes should not depend on line endings");
+        
+        // Scanned separately, so declarations don't merge across the files
+        let mut processor = ClassProcessor::new(options, &output_dir);
+        let mut classes = |file: &PathBuf| -> Result<Vec<_>> {
+            Ok(processor.process_files(std::slice::from_ref(file))?.classes.into_iter()
+                .map(|class| (class.name, class.parent, class.properties))
+                .collect())
+        };
+        assert_eq!(classes(&lf_file)?, classes(&crlf_file)?, "Both files should yield the same classes");
+        
+        let result = ClassProcessor::with_defaults(&output_dir).process_files(&files)?;
+        assert_ne!(result.file_hashes[&lf_file], result.file_hashes[&crlf_file],
+                   "Raw hashes should still differ without normalization");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_stats_by_extension() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let cpp_file = temp_dir.path().join("config.cpp");
+        let hpp_file = temp_dir.path().join("weapons.hpp");
+        let inc_file = temp_dir.path().join("macros.inc");
+        fs::write(&cpp_file, "class CfgPatches { class my_mod {}; };\n")?;
+        fs::write(&hpp_file, "class Rifle;\n")?;
+        fs::write(&inc_file, "class Helper;\n")?;
+        
+        // A missing file counts as an error against its extension
+        let missing_file = temp_dir.path().join("missing.hpp");
+        let files = vec![cpp_file, hpp_file, inc_file, missing_file];
+        
+        let result = ClassProcessor::with_defaults(&output_dir).process_files(&files)?;
+        let by_extension = &result.stats.by_extension;
+        
+        assert_eq!(by_extension.len(), 3);
+        assert_eq!(by_extension["cpp"], ExtensionStats { files: 1, classes: 2, errors: 0 });
+        assert_eq!(by_extension["hpp"], ExtensionStats { files: 2, classes: 1, errors: 1 });
+        assert_eq!(by_extension["inc"], ExtensionStats { files: 1, classes: 1, errors: 0 });
+        
+        // A declaration merged into its definition in the same file counts once
+        let declared_file = temp_dir.path().join("declared.hpp");
+        fs::write(&declared_file, "class A;\nclass A: B {};\n")?;
+        let result = ClassProcessor::with_defaults(&output_dir).process_files(&[declared_file])?;
+        assert_eq!(result.classes.len(), 1);
+        assert_eq!(result.stats.total_classes, 1);
+        assert_eq!(result.stats.by_extension["hpp"].classes, 1);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_files_below_class_threshold_are_suspicious() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let good_file = temp_dir.path().join("good.hpp");
+        let empty_file = temp_dir.path().join("empty.hpp");
+        fs::write(&good_file, "class Rifle;\n")?;
+        fs::write(&empty_file, "// nothing to see here\n")?;
+        
+        let options = ClassScanOptions {
+            warn_below_classes: Some(1),
+            ..ClassScanOptions::default()
+        };
+        let result = ClassProcessor::new(options, &output_dir)
+            .process_files(&[good_file, empty_file.clone()])?;
+        
+        assert_eq!(result.suspicious_files, vec![empty_file]);
+        assert_eq!(result.stats.error_files, 0, "Suspicious files are not errors");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_scan_result_helpers() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let class_file = temp_dir.path().join("vehicles.hpp");
+        fs::write(&class_file, "class Vehicle {};\nclass Tank: Vehicle {};\nclass Car: Vehicle {};\nclass Tank_Heavy: Tank {};\n")?;
+        
+        let result = ClassProcessor::with_defaults(&output_dir).process_files(&[class_file])?;
+        
+        assert_eq!(result.names(), vec!["Vehicle", "Tank", "Car", "Tank_Heavy"]);
+        
+        assert_eq!(result.find("Tank").and_then(|c| c.parent.as_deref()), Some("Vehicle"));
+        assert!(result.find("Boat").is_none());
+        
+        let children: Vec<&str> = result.children_of("Vehicle").iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(children, vec!["Tank", "Car"]);
+        assert!(result.children_of("Tank_Heavy").is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_scan_result_json_round_trip() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let class_file = temp_dir.path().join("vehicles.hpp");
+        fs::write(&class_file, "class Vehicle {\n    scope = 2;\n    crew[] = {\"a\", \"b\"};\n};\nclass Tank: Vehicle {};\n")?;
+        
+        let result = ClassProcessor::with_defaults(&output_dir)
+            .process_files(&[class_file, temp_dir.path().join("missing.hpp")])?;
+        
+        let json = serde_json::to_string(&result)?;
+        let restored: ClassScanResult = serde_json::from_str(&json)?;
+        
+        assert_eq!(restored, result);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_collect_defines_reports_conflicts() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let first = temp_dir.path().join("first.hpp");
+        let second = temp_dir.path().join("second.hpp");
+        fs::write(&first, "#define ARMOR_LIGHT 50\n#define MOD_PREFIX my_mod\nclass Car;\n")?;
+        fs::write(&second, "#define ARMOR_LIGHT 80\n#define MOD_PREFIX my_mod\nclass Truck;\n")?;
+        
+        let options = ClassScanOptions {
+            collect_defines: true,
+            ..ClassScanOptions::default()
+        };
+        let result = ClassProcessor::new(options, &output_dir)
+            .process_files(&[first.clone(), second.clone()])?;
+        
+        assert_eq!(result.defines.len(), 2);
+        assert_eq!(result.defines["MOD_PREFIX"], "my_mod");
+        assert_eq!(result.defines["ARMOR_LIGHT"], "50", "The first value seen is kept");
+        
+        assert_eq!(result.define_conflicts.len(), 1, "Identical redefinitions are not conflicts");
+        assert_eq!(result.define_conflicts[0].name, "ARMOR_LIGHT");
+        assert_eq!(result.define_conflicts[0].definitions, vec![
+            (first, "50".to_string()),
+            (second, "80".to_string()),
+        ]);
+        
+        // Collection is off by default
+        let result = ClassProcessor::with_defaults(&output_dir).process_files(&[temp_dir.path().join("first.hpp")])?;
+        assert!(result.defines.is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_exclude_parents_drops_subtree() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let moves_file = temp_dir.path().join("moves.hpp");
+        fs::write(&moves_file, r#"
+        class CfgMovesEarly: CfgMovesFatigue {};
+        class CfgMovesBasic;
+        class CfgMovesMaleSdr: CfgMovesBasic {
+            class States {
+                class AmovPercMstpSlowWrflDnon;
+            };
+        };
+        class CfgMovesFatigue: CfgMovesMaleSdr {};
+        class CfgVehicles {
+            class States {};
+        };
+        "#)?;
+        
+        let options = ClassScanOptions {
+            exclude_parents: vec!["CfgMovesBasic".to_string()],
+            ..ClassScanOptions::default()
+        };
+        let result = ClassProcessor::new(options, &output_dir).process_files(&[moves_file])?;
+        
+        // The sibling CfgVehicles keeps its own nested States class
+        assert_eq!(result.names(), vec!["CfgMovesBasic", "CfgVehicles", "States"]);
+        assert_eq!(result.find("States").unwrap().container_path, vec!["CfgVehicles".to_string()]);
+        // Including a class inheriting from one defined further down
+        assert_eq!(result.stats.excluded_classes, 5);
+        assert_eq!(result.stats.total_classes, 3);
+        assert_eq!(result.stats.by_extension["hpp"].classes, 3);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_long_property_values_are_truncated() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let class_file = temp_dir.path().join("ammo.hpp");
+        fs::write(&class_file, r#"
+        class Box {
+            scope = 2;
+            items[] = {"item_01", "item_02", "item_03", "item_04", "item_05"};
+        };
+        "#)?;
+        
+        let options = ClassScanOptions {
+            max_property_value_len: Some(16),
+            ..ClassScanOptions::default()
+        };
+        let result = ClassProcessor::new(options, &output_dir).process_files(&[class_file])?;
+        let class = result.find("Box").unwrap();
+        
+        assert_eq!(&*class.properties[0].1, "2", "Short values are untouched");
+        assert_eq!(&*class.properties[1].1, r#"{"item_01", "ite..."#);
+        assert_eq!(class.truncated_properties, vec!["items".to_string()]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_stats_success_rate_and_merge() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let good_file = temp_dir.path().join("good.hpp");
+        fs::write(&good_file, "class A;\nclass B;\nclass C;\n")?;
+        let other_file = temp_dir.path().join("other.cpp");
+        fs::write(&other_file, "class D;\n")?;
+        let missing_file = temp_dir.path().join("missing.hpp");
+        
+        let mut processor = ClassProcessor::with_defaults(&output_dir);
+        let first = processor.process_files(&[good_file, missing_file])?;
+        assert_eq!(first.stats.success_rate(), 0.5);
+        assert_eq!(first.stats.avg_classes_per_file(), 3.0);
+        
+        let second = processor.process_files(&[other_file])?;
+        assert_eq!(second.stats.success_rate(), 1.0);
+        
+        let mut merged = first.stats.clone();
+        merged.merge(&second.stats);
+        assert_eq!(merged.total_files, 3);
+        assert_eq!(merged.total_classes, 4);
+        assert_eq!(merged.error_files, 1);
+        assert_eq!(merged.error_file_paths, first.stats.error_file_paths);
+        assert_eq!(merged.by_extension["hpp"], ExtensionStats { files: 2, classes: 3, errors: 1 });
+        assert_eq!(merged.by_extension["cpp"], ExtensionStats { files: 1, classes: 1, errors: 0 });
+        assert!((merged.success_rate() - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(merged.avg_classes_per_file(), 2.0);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_empty_file_paths_recorded() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("output");
+        
+        let class_file = temp_dir.path().join("classes.hpp");
+        fs::write(&class_file, "class A;\n")?;
+        let empty_file = temp_dir.path().join("empty.hpp");
+        fs::write(&empty_file, "// nothing to see here\nvalue = 1;\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(&output_dir);
+        let result = processor.process_files(&[class_file, empty_file.clone()])?;
+        
+        assert_eq!(result.stats.empty_files, 1);
+        assert_eq!(result.stats.empty_file_paths, vec![empty_file]);
+        
+        Ok(())
+    }
+    
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_scan_directory_async() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let input_dir = temp_dir.path().join("input");
+        fs::create_dir_all(&input_dir)?;
+        fs::write(input_dir.join("config.cpp"), "class Base {};\nclass Child : Base {};\n")?;
+        
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = runtime.block_on(processor.scan_directory_async(&input_dir))?;
+        
+        assert_eq!(result.names(), vec!["Base", "Child"]);
+        assert_eq!(result.stats.total_files, 1);
+        assert!(processor.get_scan_errors().error_files.is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_blacklist_patterns() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class Man {};\nclass AmovPercMstpSnonWnonDnon {};\nclass AmovPknlMstpSrasWrflDnon {};\nclass Default {};\nclass DefaultDie {};\n")?;
+        
+        let options = ClassScanOptions {
+            blacklist_patterns: vec!["Amov.*".to_string(), "Default".to_string()],
+            ..ClassScanOptions::default()
+        };
+        let anims_file = temp_dir.path().join("anims.hpp");
+        fs::write(&anims_file, "class AmovPpneMstpSnonWnonDnon {};\n")?;
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        let result = processor.process_files(&[file, anims_file])?;
+        
+        // "Default" is matched exactly, so "DefaultDie" is kept
+        assert_eq!(result.names(), vec!["Man", "DefaultDie"]);
+        assert_eq!(result.stats.blacklisted, 4);
+        assert_eq!(result.stats.total_classes, 2);
+        assert_eq!(result.stats.files_with_classes, 1, "A file with every class blacklisted has none left");
+        
+        let options = ClassScanOptions {
+            blacklist_patterns: vec!["(".to_string()],
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        assert!(processor.process_files(&[]).is_err());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_identical_duplicates_are_not_conflicts() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let shared = "class CfgWeapons {\n    class Shared_Base { scope = 0; };\n};\n";
+        let first = temp_dir.path().join("first.hpp");
+        fs::write(&first, format!("{}class Tweaked {{ mass = 1; }};\n", shared))?;
+        let second = temp_dir.path().join("second.hpp");
+        fs::write(&second, format!("{}class Tweaked {{ mass = 2; }};\n", shared))?;
+        let files = vec![first.clone(), second.clone()];
+        
+        // Without deduplication, identical copies are kept but not reported
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = processor.process_files(&files)?;
+        assert_eq!(result.classes.len(), 6);
+        assert_eq!(result.class_conflicts, vec![ClassConflict {
+            name: "Tweaked".to_string(),
+            container_path: Vec::new(),
+            files: vec![Some(first.clone()), Some(second.clone())],
+        }]);
+        
+        let options = ClassScanOptions {
+            deduplicate_identical: true,
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        let result = processor.process_files(&files)?;
+        assert_eq!(result.names(), vec!["CfgWeapons", "Shared_Base", "Tweaked", "Tweaked"]);
+        assert_eq!(result.stats.duplicate_classes, 2);
+        assert_eq!(result.stats.total_classes, 4);
+        assert_eq!(result.class_conflicts.len(), 1);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_resolve_parents_across_files() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let base_file = temp_dir.path().join("base.hpp");
+        fs::write(&base_file, "class Vehicle_Base {};\n")?;
+        let child_file = temp_dir.path().join("child.hpp");
+        fs::write(&child_file, "class Car : Vehicle_Base {};\nclass Plane : External_Base {};\n")?;
+        
+        let options = ClassScanOptions {
+            resolve_parents: true,
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        let result = processor.process_files(&[base_file.clone(), child_file])?;
+        
+        assert_eq!(result.find("Car").unwrap().resolved_parent, Some(base_file));
+        assert_eq!(result.find("Plane").unwrap().resolved_parent, None);
+        assert_eq!(result.find("Vehicle_Base").unwrap().resolved_parent, None);
+        
+        // A parent that is only forward-declared is not defined in the scan
+        let declared_file = temp_dir.path().join("declared.hpp");
+        fs::write(&declared_file, "class Truck_Base;\n")?;
+        let truck_file = temp_dir.path().join("truck.hpp");
+        fs::write(&truck_file, "class Truck : Truck_Base {};\n")?;
+        let result = processor.process_files(&[declared_file, truck_file])?;
+        assert_eq!(result.find("Truck").unwrap().resolved_parent, None);
+        
+        // Resolution is off by default
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = processor.scan_directory(temp_dir.path())?;
+        assert!(result.classes.iter().all(|c| c.resolved_parent.is_none()));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_forward_declarations_merge_with_definitions() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let header = temp_dir.path().join("header.hpp");
+        fs::write(&header, "class Parent;\nclass Child : Parent;\nclass Orphan : Parent;\nclass Orphan : Parent;\n")?;
+        let config = temp_dir.path().join("config.cpp");
+        fs::write(&config, "class Parent {};\nclass Child : Parent {\n    scope = 2;\n};\n")?;
+        
+        // By default declarations only merge within their own file
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = processor.process_files(&[header.clone(), config.clone()])?;
+        assert_eq!(result.names(), vec!["Parent", "Child", "Orphan", "Parent", "Child"]);
+        assert_eq!(result.stats.merged_declarations, 1);
+        assert_eq!(result.stats.total_classes, 5);
+        let declared: Vec<&str> = result.classes.iter()
+            .filter(|class| class.file_path.as_deref() == Some(header.as_path()))
+            .map(|class| class.name.as_str())
+            .collect();
+        assert_eq!(declared, vec!["Parent", "Child", "Orphan"], "The header keeps its declarations");
+        
+        let options = ClassScanOptions {
+            merge_declarations_across_files: true,
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        let result = processor.process_files(&[header.clone(), config.clone()])?;
+        
+        // Declarations fold into the definitions; the repeated declaration into the first
+        assert_eq!(result.names(), vec!["Orphan", "Parent", "Child"]);
+        assert_eq!(result.stats.merged_declarations, 3);
+        assert_eq!(result.stats.total_classes, 3);
+        assert!(result.class_conflicts.is_empty());
+        
+        let child = result.find("Child").unwrap();
+        assert_eq!(child.parent.as_deref(), Some("Parent"));
+        assert!(!child.is_forward_declaration);
+        assert_eq!(child.file_path.as_deref(), Some(config.as_path()));
+        
+        let orphan = result.find("Orphan").unwrap();
+        assert!(orphan.is_forward_declaration);
+        assert_eq!(orphan.parent.as_deref(), Some("Parent"));
+        
+        // A declaration scanned later doesn't replace the stored definition
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(result)?;
+        let declarations = processor.process_files(&[header])?;
+        db_ops.update_with_scan_results(declarations)?;
+        
+        let stored = db_ops.get_class("Child").unwrap();
+        assert!(!stored.class.is_forward_declaration);
+        assert_eq!(stored.class.properties.len(), 1);
+        assert_eq!(stored.class.parent.as_deref(), Some("Parent"));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_output_dir_is_created() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path().join("nested").join("output");
+        
+        let mut processor = ClassProcessor::with_defaults(&output_dir);
+        processor.process_files(&[])?;
+        assert!(output_dir.is_dir());
+        
+        // A file in the way gives a clear error instead of failing later
+        let blocked = temp_dir.path().join("blocked");
+        fs::write(&blocked, "")?;
+        let mut processor = ClassProcessor::with_defaults(blocked.join("output"));
+        let err = processor.process_files(&[]).unwrap_err();
+        assert!(err.to_string().contains("Output directory"));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_merge_scan_results() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let first_dir = temp_dir.path().join("first");
+        let second_dir = temp_dir.path().join("second");
+        fs::create_dir_all(&first_dir)?;
+        fs::create_dir_all(&second_dir)?;
+        fs::write(first_dir.join("a.hpp"), "class Shared { scope = 1; };\nclass A : Shared {};\n")?;
+        fs::write(second_dir.join("b.hpp"), "class Shared { scope = 1; };\nclass B : Shared {};\nclass A { scope = 2; };\n")?;
+        fs::write(second_dir.join("broken.hpp"), [0xff, 0xfe, 0x00])?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let mut merged = processor.scan_directory(&first_dir)?;
+        let second = processor.scan_directory(&second_dir)?;
+        merged.merge(second, &ClassScanOptions::default());
+        
+        assert_eq!(merged.names(), vec!["Shared", "A", "Shared", "B", "A"]);
+        assert_eq!(merged.stats.total_files, 3);
+        assert_eq!(merged.stats.total_classes, 5);
+        assert_eq!(merged.stats.error_files, 1);
+        assert_eq!(merged.file_hashes.len(), 2);
+        
+        // Only the differing duplicate is a conflict
+        let conflicts: Vec<&str> = merged.class_conflicts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(conflicts, vec!["A"]);
+        
+        assert_eq!(merged.deduplicate(), 1);
+        assert_eq!(merged.names(), vec!["Shared", "A", "B", "A"]);
+        assert_eq!(merged.stats.total_classes, 4);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_merge_scan_results_with_conflict_policy() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let first = temp_dir.path().join("first.hpp");
+        let second = temp_dir.path().join("second.hpp");
+        fs::write(&first, "class Shared { scope = 1; };\nclass Rifle: Rifle_Base;\n")?;
+        fs::write(&second, "class Shared { scope = 1; };\nclass Rifle { scope = 2; };\n")?;
+        
+        let options = ClassScanOptions {
+            deduplicate_identical: true,
+            merge_declarations_across_files: true,
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options.clone(), temp_dir.path().join("output"));
+        let mut merged = processor.process_files(std::slice::from_ref(&first))?;
+        let other = processor.process_files(std::slice::from_ref(&second))?;
+        merged.merge(other, &options);
+        
+        // The identical duplicate is dropped rather than reported, and the
+        // declaration from the first scan folds into the second's definition
+        assert!(merged.class_conflicts.is_empty(), "{:?}", merged.class_conflicts);
+        assert_eq!(merged.names(), vec!["Shared", "Rifle"]);
+        assert_eq!(merged.find("Rifle").unwrap().parent.as_deref(), Some("Rifle_Base"));
+        assert_eq!(merged.stats.duplicate_classes, 1);
+        assert_eq!(merged.stats.merged_declarations, 1);
+        assert_eq!(merged.stats.total_classes, 2);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_scan_result_summary() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let good = temp_dir.path().join("good.hpp");
+        fs::write(&good, "class A {};\nclass B {};\nclass C {};\n")?;
+        let empty = temp_dir.path().join("empty.cpp");
+        fs::write(&empty, "// nothing\n")?;
+        let missing = temp_dir.path().join("missing.hpp");
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = processor.process_files(&[good, empty, missing.clone()])?;
+        let summary = result.summary();
+        
+        assert!(summary.contains("Files scanned: 3"));
+        assert!(summary.contains("Classes found: 3 (1.5 per file)"));
+        assert!(summary.contains("Errors: 1"));
+        assert!(summary.contains("Timeouts: 0"));
+        assert!(summary.contains("Success rate: 66.7%"));
+        assert!(summary.contains("  .hpp: 1"));
+        assert!(summary.contains(&missing.display().to_string()));
+        
+        Ok(())
+    }
+    
+    /// Treats every non-empty line as the name of a class
+    #[derive(Debug)]
+    struct LineExtractor;
+    
+    impl ClassExtractor for LineExtractor {
+        fn extract(&self, content: &str, path: &Path) -> Result<Vec<ClassBlock>> {
+            Ok(content.lines()
+                .filter(|line| !line.trim().is_empty())
+                .enumerate()
+                .map(|(i, line)| ClassBlock {
+                    name: line.trim().to_string(),
+                    is_definition: true,
+                    line: i + 1,
+                    column: 1,
+                    file_path: path.to_path_buf(),
+                    ..Default::default()
+                })
+                .collect())
+        }
+    }
+    
+    #[test]
+    fn test_custom_extractor() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("names.hpp");
+        fs::write(&file, "Alpha\n\nBravo\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"))
+            .with_parser(Box::new(LineExtractor));
+        let result = processor.process_files(std::slice::from_ref(&file))?;
+        
+        assert_eq!(result.names(), vec!["Alpha", "Bravo"]);
+        assert_eq!(result.find("Bravo").unwrap().file_path.as_deref(), Some(file.as_path()));
+        
+        Ok(())
+    }
+    
+    /// Wraps `LineExtractor`, counting how often it runs
+    #[derive(Debug)]
+    struct CountingExtractor {
+        calls: Arc<AtomicUsize>,
+    }
+    
+    impl ClassExtractor for CountingExtractor {
+        fn extract(&self, content: &str, path: &Path) -> Result<Vec<ClassBlock>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            LineExtractor.extract(content, path)
+        }
+    }
+    
+    #[test]
+    fn test_cache_parsed_content() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let first = temp_dir.path().join("mod_a").join("names.hpp");
+        let second = temp_dir.path().join("mod_b").join("names.hpp");
+        for file in [&first, &second] {
+            fs::create_dir_all(file.parent().unwrap())?;
+            fs::write(file, "Alpha\nBravo\n")?;
+        }
+        let files = vec![first.clone(), second.clone()];
+        
+        let calls = Arc::new(AtomicUsize::new(0));
+        let options = ClassScanOptions {
+            cache_parsed_content: true,
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"))
+            .with_parser(Box::new(CountingExtractor { calls: calls.clone() }));
+        let result = processor.process_files(&files)?;
+        
+        assert_eq!(calls.load(Ordering::SeqCst), 1, "Identical content should be parsed once");
+        assert_eq!(result.file_hashes.len(), 2);
+        
+        // Cached classes are labelled with the file they were reused for
+        let mut paths: Vec<_> = result.classes.iter()
+            .filter(|class| class.name == "Bravo")
+            .filter_map(|class| class.file_path.clone())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, files);
+        
+        // Without the cache every file is parsed
+        calls.store(0, Ordering::SeqCst);
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"))
+            .with_parser(Box::new(CountingExtractor { calls: calls.clone() }));
+        processor.process_files(&files)?;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_min_class_name_len() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class a;\nclass Rifle {\n    class x {};\n};\nclass Mk {};\n")?;
+        
+        let options = ClassScanOptions {
+            min_class_name_len: 3,
+            ..ClassScanOptions::default()
+        };
+        let short_file = temp_dir.path().join("short.hpp");
+        fs::write(&short_file, "class a;\nclass b {};\n")?;
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        let result = processor.process_files(&[file.clone(), short_file])?;
+        
+        assert_eq!(result.names(), vec!["Rifle"]);
+        assert_eq!(result.stats.filtered, 5);
+        assert_eq!(result.stats.total_classes, 1);
+        assert_eq!(result.stats.files_with_classes, 1, "A file with every class filtered out has none left");
+        
+        // Every name is kept by default
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = processor.process_files(&[file])?;
+        assert_eq!(result.stats.filtered, 0);
+        assert_eq!(result.classes.len(), 4);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_sample_files() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let files: Vec<PathBuf> = (0..20)
+            .map(|i| {
+                let file = temp_dir.path().join(format!("config_{:02}.cpp", i));
+                fs::write(&file, format!("class Class_{:02} {{}};\n", i)).map(|_| file)
+            })
+            .collect::<std::io::Result<_>>()?;
+        
+        let sample = |seed: u64| -> Result<Vec<String>> {
+            let options = ClassScanOptions {
+                sample_files: Some(5),
+                sample_seed: Some(seed),
+                ..ClassScanOptions::default()
+            };
+            let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+            let result = processor.process_files(&files)?;
+            assert_eq!(result.stats.total_files, 5);
+            Ok(result.names().into_iter().map(str::to_string).collect())
+        };
+        
+        let first = sample(42)?;
+        assert_eq!(first.len(), 5);
+        assert_eq!(sample(42)?, first, "A fixed seed should pick the same files");
+        
+        // Asking for more files than there are scans everything
+        let options = ClassScanOptions {
+            sample_files: Some(50),
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        assert_eq!(processor.process_files(&files)?.classes.len(), 20);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_benchmark_files() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let rifles = temp_dir.path().join("rifles.hpp");
+        let vehicles = temp_dir.path().join("vehicles.cpp");
+        fs::write(&rifles, "class Rifle_Base;\nclass Rifle: Rifle_Base {};\n")?;
+        fs::write(&vehicles, "class CfgVehicles {\n    class Car {};\n    class Truck {};\n};\n")?;
+        let missing = temp_dir.path().join("missing.hpp");
+        
+        let processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let benches = processor.benchmark_files(&[rifles.clone(), vehicles.clone(), missing.clone()]);
+        
+        let summary: Vec<(&PathBuf, usize)> = benches.iter().map(|FileBench { path, classes, .. }| (path, *classes)).collect();
+        assert_eq!(summary, vec![(&rifles, 2), (&vehicles, 3), (&missing, 0)]);
+        assert!(benches.iter().all(|bench| !bench.duration.is_zero()));
+        assert!(!temp_dir.path().join("output").exists(), "Benchmarking shouldn't write output");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_class_ids() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let first = temp_dir.path().join("first.hpp");
+        let second = temp_dir.path().join("second.hpp");
+        fs::write(&first, "class Car {};\nclass CfgVehicles {\n    class Car {};\n};\n")?;
+        fs::write(&second, "class Car {};\n")?;
+        let files = vec![first, second];
+        
+        let ids = || -> Result<Vec<String>> {
+            let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+            Ok(processor.process_files(&files)?.classes.iter()
+                .filter(|class| class.name == "Car")
+                .map(|class| class.id())
+                .collect())
+        };
+        
+        let run = ids()?;
+        assert_eq!(run.len(), 3);
+        assert_eq!(ids()?, run, "IDs should be stable across scans");
+        
+        let unique: std::collections::HashSet<&String> = run.iter().collect();
+        assert_eq!(unique.len(), 3, "Same-named classes in other files or containers get distinct IDs");
+        assert!(run.iter().all(|id| id.len() == 16));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_path_root_stores_relative_paths() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let root = temp_dir.path().join("mods");
+        fs::create_dir_all(root.join("weapons"))?;
+        let inside = root.join("weapons").join("rifles.hpp");
+        let outside = temp_dir.path().join("loose.hpp");
+        fs::write(&inside, "class Rifle {};\n")?;
+        fs::write(&outside, "class Loose {};\n")?;
+        
+        let options = ClassScanOptions {
+            path_root: Some(root.clone()),
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        let result = processor.process_files(&[inside.clone(), outside.clone()])?;
+        
+        let rifle_path = result.find("Rifle").unwrap().file_path.clone().unwrap();
+        assert_eq!(rifle_path, Path::new("weapons").join("rifles.hpp"));
+        assert!(rifle_path.is_relative());
+        assert_eq!(root.join(&rifle_path), inside, "Joining with the root should give the scanned file");
+        assert_eq!(result.find("Loose").unwrap().file_path.as_deref(), Some(outside.as_path()), "Files outside the root keep their full path");
+        assert!(result.file_hashes.contains_key(&rifle_path));
+        
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(result)?;
+        assert_ne!(db_ops.get_class("Rifle").unwrap().file_hash, "unknown", "The scan's hash should be matched to the relative path");
+        assert_eq!(db_ops.get_classes_in_file(&rifle_path).len(), 1);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_relative_path_collision_between_roots() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        let scan = |root: &Path, class: &str| -> Result<ClassScanResult> {
+            fs::create_dir_all(root)?;
+            let file = root.join("config.cpp");
+            fs::write(&file, format!("class {} {{}};\n", class))?;
+            let options = ClassScanOptions {
+                path_root: Some(root.to_path_buf()),
+                ..ClassScanOptions::default()
+            };
+            ClassProcessor::new(options, temp_dir.path().join("output")).process_files(&[file])
+        };
+        
+        let root_a = temp_dir.path().join("mods").join("ace");
+        let root_b = temp_dir.path().join("mods").join("cba");
+        db_ops.update_with_scan_results(scan(&root_a, "Ace_Main")?)?;
+        
+        // Both roots contain config.cpp, which would otherwise share one key
+        let err = db_ops.update_with_scan_results(scan(&root_b, "Cba_Main")?).unwrap_err();
+        assert!(err.to_string().contains("config.cpp"));
+        assert!(db_ops.get_class("Cba_Main").is_none());
+        let in_file: Vec<&str> = db_ops.get_classes_in_file("config.cpp").iter().map(|e| e.class.name.as_str()).collect();
+        assert_eq!(in_file, vec!["Ace_Main"], "The stored file should be left untouched");
+        
+        // Rescanning from the same root is fine
+        db_ops.update_with_scan_results(scan(&root_a, "Ace_Main_F")?)?;
+        assert_eq!(db_ops.get_classes_in_file("config.cpp").len(), 1);
+        
+        // A file stored before roots were tracked has an unknown root, which
+        // doesn't count as a collision
+        db_ops.database_mut().file_roots.clear();
+        db_ops.update_with_scan_results(scan(&root_b, "Cba_Main")?)?;
+        let in_file: Vec<&str> = db_ops.get_classes_in_file("config.cpp").iter().map(|e| e.class.name.as_str()).collect();
+        assert_eq!(in_file, vec!["Cba_Main"]);
+        assert!(db_ops.update_with_scan_results(scan(&root_a, "Ace_Main")?).is_err(), "The root is recorded again");
+        
+        // A common root keeps the files apart
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("common.json"))?;
+        let options = ClassScanOptions {
+            path_root: Some(temp_dir.path().join("mods")),
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        db_ops.update_with_scan_results(processor.process_files(&[root_a.join("config.cpp"), root_b.join("config.cpp")])?)?;
+        assert_eq!(db_ops.get_classes_in_file(Path::new("ace").join("config.cpp")).len(), 1);
+        assert_eq!(db_ops.get_classes_in_file(Path::new("cba").join("config.cpp")).len(), 1);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_numeric_values_stored_verbatim() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class Rifle {\n    dispersion = 0.634570;\n    mass = 1e3;\n    scale = -0.50;\n    recoil[] = {0.100, 2.0E-2};\n};\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = processor.process_files(&[file])?;
+        
+        let db_path = temp_dir.path().join("db.json");
+        let mut db_ops = DatabaseOperations::new(&db_path)?;
+        db_ops.update_with_scan_results(result)?;
+        db_ops.save()?;
+        
+        // Values must survive a save and load exactly as written in the source
+        let reloaded = DatabaseOperations::new(&db_path)?;
+        let properties: Vec<(String, String)> = reloaded.get_class("Rifle").unwrap().class.properties.iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect();
+        assert_eq!(properties, vec![
+            ("dispersion".to_string(), "0.634570".to_string()),
+            ("mass".to_string(), "1e3".to_string()),
+            ("scale".to_string(), "-0.50".to_string()),
+            ("recoil".to_string(), "{0.100, 2.0E-2}".to_string()),
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_structure_only_scan() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class CfgVehicles {\n    class Car;\n    class Truck: Car {\n        scope = 2;\n        displayName = \"Truck\";\n        hiddenSelections[] = {\"camo\"};\n    };\n};\n")?;
+        
+        let scan = |scan_mode: ScanMode| -> Result<ClassScanResult> {
+            let options = ClassScanOptions {
+                scan_mode,
+                ..ClassScanOptions::default()
+            };
+            ClassProcessor::new(options, temp_dir.path().join("output")).process_files(std::slice::from_ref(&file))
+        };
+        let full = scan(ScanMode::Full)?;
+        let structure = scan(ScanMode::StructureOnly)?;
+        
+        assert_eq!(full.find("Truck").unwrap().properties.len(), 3);
+        assert!(structure.classes.iter().all(|class| class.properties.is_empty()), "No properties should be captured");
+        
+        let shape = |result: &ClassScanResult| -> Vec<(String, Option<String>, Vec<String>, bool)> {
+            result.classes.iter()
+                .map(|c| (c.name.clone(), c.parent.clone(), c.container_path.clone(), c.is_forward_declaration))
+                .collect()
+        };
+        assert_eq!(shape(&structure), shape(&full), "Names, parents and nesting should match a full scan");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_max_error_rate() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut files = Vec::new();
+        for i in 0..3 {
+            let file = temp_dir.path().join(format!("good_{}.hpp", i));
+            fs::write(&file, format!("class Good_{} {{}};\n", i))?;
+            files.push(file);
+        }
+        // Invalid UTF-8 can't be read, so this file fails to parse
+        let bad = temp_dir.path().join("bad.hpp");
+        fs::write(&bad, [0xff, 0xfe, 0x00, 0x63])?;
+        files.push(bad);
+        
+        let scan = |max_error_rate: Option<f64>| {
+            let options = ClassScanOptions {
+                max_error_rate,
+                ..ClassScanOptions::default()
+            };
+            ClassProcessor::new(options, temp_dir.path().join("output")).process_files(&files)
+        };
+        
+        // One of four files failed: 25%
+        let err = scan(Some(0.1)).unwrap_err();
+        assert!(err.to_string().contains("1 of 4 files failed"), "{}", err);
+        assert_eq!(scan(Some(0.25))?.classes.len(), 3);
+        assert_eq!(scan(None)?.stats.error_files, 1);
+        
+        Ok(())
+    }
+}
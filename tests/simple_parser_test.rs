@@ -6,9 +6,10 @@ mod tests {
     use tempfile::tempdir;
     
     // Add required imports
-    use code_scanner::class::scanner::simple_parser::SimpleParser;
+    use code_scanner::class::scanner::simple_parser::{SimpleParser, parse_class_line};
     use code_scanner::class::processor::ClassProcessor;
     use code_scanner::class::types::ClassScanOptions;
+    use code_scanner::utils::file_utils;
     
     #[test]
     fn test_simple_parser() -> Result<()> {
@@ -64,6 +65,537 @@ mod tests {
         Ok(())
     }
     
+    #[test]
+    fn test_array_whitespace_normalization() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let tight_path = temp_dir.path().join("tight.hpp");
+        let loose_path = temp_dir.path().join("loose.hpp");
+        
+        fs::write(&tight_path, r#"
+        class Rifle {
+            magazines[] = {"a","b","c"};
+            hiddenSelections[] = {"camo"};
+        };
+        "#)?;
+        fs::write(&loose_path, r#"
+        class Rifle {
+            magazines[] = {"a",  "b" ,"c"};
+            hiddenSelections[] =
+            {
+                "camo"
+            };
+        };
+        "#)?;
+        
+        let parser = SimpleParser::new(false);
+        let tight = parser.parse_file(&tight_path)?;
+        let loose = parser.parse_file(&loose_path)?;
+        
+        assert_eq!(tight[0].properties, loose[0].properties, "Differently spaced arrays should be stored identically");
+        assert_eq!(tight[0].properties[0], ("magazines".to_string(), r#"{"a", "b", "c"}"#.to_string()));
+        assert_eq!(tight[0].properties[1], ("hiddenSelections".to_string(), r#"{"camo"}"#.to_string()));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_enum_blocks_do_not_affect_containers() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("config.cpp");
+        
+        fs::write(&file_path, r#"
+        class Car_Base;
+        class CfgVehicles {
+            class Car: Car_Base {
+                scope = 2;
+            };
+            enum {
+                // class Fake: NotAClass {
+                DESTRUCTENGINE = 2,
+                DESTRUCTDEFAULT = 6,
+                DESTRUCTNO
+            };
+            class Truck: Car {
+                class Turrets {};
+            };
+        };
+        class CfgWeapons {};
+        "#)?;
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_file(&file_path)?;
+        let names: Vec<&str> = classes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Car_Base", "CfgVehicles", "Car", "Truck", "Turrets", "CfgWeapons"]);
+        
+        let find = |name: &str| classes.iter().find(|c| c.name == name).unwrap();
+        assert!(find("CfgVehicles").container_path.is_empty());
+        assert_eq!(find("Car").container_path, vec!["CfgVehicles".to_string()]);
+        assert_eq!(find("Truck").container_path, vec!["CfgVehicles".to_string()]);
+        assert_eq!(find("Truck").parent, Some("Car".to_string()));
+        assert_eq!(find("Turrets").container_path, vec!["CfgVehicles".to_string(), "Truck".to_string()]);
+        assert!(find("CfgWeapons").container_path.is_empty());
+        assert!(find("CfgVehicles").properties.is_empty(), "Enum members are not properties");
+        
+        let enums = parser.parse_enums(&fs::read_to_string(&file_path)?);
+        assert_eq!(enums.len(), 1);
+        assert_eq!(enums[0].members, vec![
+            ("DESTRUCTENGINE".to_string(), Some("2".to_string())),
+            ("DESTRUCTDEFAULT".to_string(), Some("6".to_string())),
+            ("DESTRUCTNO".to_string(), None),
+        ]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_macro_wrapped_parent() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("weapons.hpp");
+        
+        fs::write(&file_path, r#"
+        #define BASECLASS(type) Weapon_##type##_Base
+        #define PISTOL_BASE Weapon_pistol_Base
+        #define ALIAS BASECLASS(launcher)
+        class Weapon_rifle_Base;
+        class Rifle: BASECLASS(rifle) {
+            scope = 2;
+        };
+        class Pistol: PISTOL_BASE {};
+        class Launcher: ALIAS {};
+        class Unknown: NOT_DEFINED(x) {};
+        "#)?;
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_file(&file_path)?;
+        let parent_of = |name: &str| classes.iter().find(|c| c.name == name).unwrap().parent.clone();
+        
+        assert_eq!(parent_of("Rifle"), Some("Weapon_rifle_Base".to_string()));
+        assert_eq!(parent_of("Pistol"), Some("Weapon_pistol_Base".to_string()));
+        assert_eq!(parent_of("Launcher"), Some("Weapon_launcher_Base".to_string()));
+        assert_eq!(parent_of("Unknown"), Some("NOT_DEFINED(x)".to_string()), "Unknown macros are kept verbatim");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_custom_class_pattern() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("odd_names.hpp");
+        fs::write(&file_path, "class Base;\nclass my-rifle.mk2: Base {};\n")?;
+        
+        // The default identifier rules miss the dashed and dotted name
+        let default_names: Vec<String> = SimpleParser::new(false).parse_file(&file_path)?
+            .into_iter().map(|c| c.name).collect();
+        assert_eq!(default_names, vec!["Base".to_string()]);
+        
+        let parser = SimpleParser::new(false)
+            .with_pattern(r"class\s+([A-Za-z0-9_.\-]+)(?:\s*:\s*([A-Za-z0-9_]+))?[\s{;]")?;
+        let classes = parser.parse_file(&file_path)?;
+        let custom = classes.iter().find(|c| c.name == "my-rifle.mk2").expect("Custom pattern should match");
+        assert_eq!(custom.parent, Some("Base".to_string()));
+        
+        // Invalid patterns and patterns without a name group are rejected
+        assert!(SimpleParser::new(false).with_pattern(r"class\s+(").is_err());
+        assert!(SimpleParser::new(false).with_pattern(r"class\s+\w+").is_err());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_capture_name_spans() -> Result<()> {
+        let content = "// header\nclass Rifle_Base;\nclass  Rifle : Rifle_Base {\n    class Scope {};\n};\n";
+        
+        let parser = SimpleParser::from_options(&ClassScanOptions {
+            capture_spans: true,
+            ..ClassScanOptions::default()
+        });
+        let classes = parser.parse_content(content.to_string(), Path::new("spans.hpp"))?;
+        assert_eq!(classes.len(), 3);
+        
+        for class in &classes {
+            let (start, end) = class.name_span.expect("Spans should be captured");
+            assert_eq!(&content[start..end], class.name);
+            let (start, end) = class.keyword_span.expect("Keyword span should be captured");
+            assert_eq!(&content[start..end], "class");
+        }
+        
+        // Spans are off by default
+        let classes = SimpleParser::new(false).parse_content(content.to_string(), Path::new("spans.hpp"))?;
+        assert!(classes.iter().all(|c| c.name_span.is_none() && c.keyword_span.is_none()));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_capture_annotations() -> Result<()> {
+        let content = r#"
+class CfgWeapons {
+    // @category weapons
+    // Standard issue rifle
+    // @faction blufor
+    class Rifle_Base {
+        scope = 1;
+    };
+    
+    // @category unused
+    
+    class Pistol_Base {};
+};
+"#;
+        
+        let parser = SimpleParser::from_options(&ClassScanOptions {
+            capture_annotations: true,
+            ..ClassScanOptions::default()
+        });
+        let classes = parser.parse_content(content.to_string(), Path::new("annotated.hpp"))?;
+        
+        let rifle = classes.iter().find(|c| c.name == "Rifle_Base").unwrap();
+        assert_eq!(rifle.annotations, vec![
+            ("category".to_string(), "weapons".to_string()),
+            ("faction".to_string(), "blufor".to_string()),
+        ]);
+        
+        // A blank line separates the comment from the class
+        let pistol = classes.iter().find(|c| c.name == "Pistol_Base").unwrap();
+        assert!(pistol.annotations.is_empty());
+        
+        let weapons = classes.iter().find(|c| c.name == "CfgWeapons").unwrap();
+        assert!(weapons.annotations.is_empty());
+        
+        // Annotations are off by default
+        let classes = SimpleParser::new(false).parse_content(content.to_string(), Path::new("annotated.hpp"))?;
+        assert!(classes.iter().all(|c| c.annotations.is_empty()));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_empty_body_is_definition() -> Result<()> {
+        let content = "class Declared;\nclass Empty {};\nclass EmptyChild : Empty\n{\n};\nclass Full { scope = 2; };\n";
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_content(content.to_string(), Path::new("bodies.hpp"))?;
+        assert_eq!(classes.len(), 4);
+        
+        let declared = &classes[0];
+        assert!(!declared.is_definition);
+        assert!(declared.properties.is_empty());
+        
+        for empty in &classes[1..3] {
+            assert!(empty.is_definition, "{} should be a definition", empty.name);
+            assert!(empty.properties.is_empty());
+        }
+        
+        assert!(classes[3].is_definition);
+        assert_eq!(classes[3].properties.len(), 1);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_conditional_branches() -> Result<()> {
+        let content = r#"
+class CfgPatches {
+#ifdef ACE_LOADED
+    class AceOnly {};
+    #ifndef LEGACY
+    class AceModern {};
+    #else
+    class AceLegacy {};
+    #endif
+#else
+    class Vanilla {};
+#endif
+#define LOCAL_FLAG
+#ifdef LOCAL_FLAG
+    class LocalFlagged {};
+#endif
+#ifdef MISSING_FLAG
+    class Missing {};
+#endif
+};
+"#;
+        
+        let names = |parser: &SimpleParser| -> Result<Vec<String>> {
+            Ok(parser.parse_content(content.to_string(), Path::new("conditional.hpp"))?
+                .into_iter()
+                .map(|c| c.name)
+                .collect())
+        };
+        
+        let parser = SimpleParser::from_options(&ClassScanOptions {
+            defined_symbols: ["ACE_LOADED".to_string()].into_iter().collect(),
+            ..ClassScanOptions::default()
+        });
+        assert_eq!(names(&parser)?, vec!["CfgPatches", "AceOnly", "AceModern", "LocalFlagged"]);
+        
+        let parser = SimpleParser::new(false);
+        assert_eq!(names(&parser)?, vec!["CfgPatches", "Vanilla", "LocalFlagged"]);
+        
+        // Blanking keeps offsets, so nesting is still computed from the original layout
+        let classes = parser.parse_content(content.to_string(), Path::new("conditional.hpp"))?;
+        assert_eq!(classes[1].container_path, vec!["CfgPatches"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_bom_prefixed_file() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("bom.hpp");
+        fs::write(&file, "\u{FEFF}class First {\n    scope = 2;\n};\nclass Second;\n")?;
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_file(&file)?;
+        assert_eq!(classes.len(), 2);
+        
+        assert_eq!(classes[0].name, "First");
+        assert_eq!(classes[0].line, 1);
+        assert_eq!(classes[0].properties, vec![("scope".to_string(), "2".to_string())]);
+        assert_eq!(classes[1].name, "Second");
+        assert_eq!(classes[1].line, 4);
+        
+        assert!(!file_utils::read_file_to_string(&file)?.starts_with(file_utils::UTF8_BOM));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_multiple_classes_on_one_line() -> Result<()> {
+        let content = "class A; class B; class C;\nclass Outer { class Inner { x = 1; }; }; class Next : Outer {};\n";
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_content(content.to_string(), Path::new("minified.hpp"))?;
+        
+        let names: Vec<&str> = classes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["A", "B", "C", "Outer", "Inner", "Next"]);
+        
+        let positions: Vec<(usize, usize)> = classes.iter().map(|c| (c.line, c.column)).collect();
+        assert_eq!(positions, vec![(1, 1), (1, 10), (1, 19), (2, 1), (2, 15), (2, 42)]);
+        
+        // Braces on a shared line are still attributed to the right class
+        assert_eq!(classes[4].container_path, vec!["Outer"]);
+        assert_eq!(classes[4].properties, vec![("x".to_string(), "1".to_string())]);
+        assert!(classes[3].properties.is_empty());
+        assert!(classes[5].container_path.is_empty());
+        assert_eq!(classes[5].parent.as_deref(), Some("Outer"));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_quoted_parent() -> Result<()> {
+        let content = "class Generated : \"Base\" {};\nclass Declared:\"Base\";\nclass Plain : Base {};\n";
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_content(content.to_string(), Path::new("generated.hpp"))?;
+        
+        let parents: Vec<(&str, Option<&str>)> = classes.iter()
+            .map(|c| (c.name.as_str(), c.parent.as_deref()))
+            .collect();
+        assert_eq!(parents, vec![
+            ("Generated", Some("Base")),
+            ("Declared", Some("Base")),
+            ("Plain", Some("Base")),
+        ]);
+        assert!(classes[0].is_definition);
+        assert!(!classes[1].is_definition);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_undef_stops_macro_expansion() -> Result<()> {
+        let content = r#"
+#define RIFLE_BASE Rifle_Base
+#define ACE_LOADED
+class Rifle: RIFLE_BASE {};
+#undef RIFLE_BASE
+#undef ACE_LOADED
+class Carbine: RIFLE_BASE {};
+#ifdef ACE_LOADED
+class AceRifle: Rifle {};
+#endif
+#define RIFLE_BASE Rifle_Base_F
+class Pistol: RIFLE_BASE {};
+"#;
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_content(content.to_string(), Path::new("undef.hpp"))?;
+        let parent_of = |name: &str| classes.iter().find(|c| c.name == name).unwrap().parent.clone();
+        
+        assert_eq!(parent_of("Rifle"), Some("Rifle_Base".to_string()));
+        assert_eq!(parent_of("Carbine"), Some("RIFLE_BASE".to_string()), "Usage after #undef is left unexpanded");
+        assert_eq!(parent_of("Pistol"), Some("Rifle_Base_F".to_string()));
+        assert!(classes.iter().all(|c| c.name != "AceRifle"), "An #undef'd symbol no longer counts as defined");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_nested_multiline_array() -> Result<()> {
+        let content = r#"
+class Turret {
+    matrix[] = {
+        {1, 2},
+        {3, 4}
+    };
+    labels[] = {"a}", "{b",};
+    weights[] = {{1,2},{3,4}} 5;
+    scope = 2;
+};
+"#;
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_content(content.to_string(), Path::new("arrays.hpp"))?;
+        let property = |name: &str| classes[0].properties.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone());
+        
+        assert_eq!(property("matrix"), Some("{{1, 2}, {3, 4}}".to_string()));
+        assert_eq!(property("labels"), Some(r#"{"a}", "{b"}"#.to_string()), "Braces in strings and a trailing comma are tolerated");
+        assert_eq!(property("weights"), Some("{{1, 2}, {3, 4}}".to_string()), "Content after the closing brace is dropped");
+        assert_eq!(property("scope"), Some("2".to_string()));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_parse_class_line() {
+        let parsed = |name: &str, parent: Option<&str>| Some((name.to_string(), parent.map(str::to_string)));
+        
+        assert_eq!(parse_class_line("class A;"), parsed("A", None));
+        assert_eq!(parse_class_line("class A: B {"), parsed("A", Some("B")));
+        assert_eq!(parse_class_line("    class Rifle_F : \"Rifle_Base\" {};"), parsed("Rifle_F", Some("Rifle_Base")));
+        assert_eq!(parse_class_line("class A"), parsed("A", None));
+        assert_eq!(parse_class_line("class A: BASE(rifle) {"), parsed("A", Some("BASE(rifle)")));
+        
+        assert_eq!(parse_class_line(""), None);
+        assert_eq!(parse_class_line("scope = 2;"), None);
+        assert_eq!(parse_class_line("displayName = \"class A;\";"), None);
+        assert_eq!(parse_class_line("// class A;"), None);
+    }
+    
+    #[test]
+    fn test_capture_raw_matches() -> Result<()> {
+        let content = "class Rifle_Base;\nclass Rifle : \"Rifle_Base\" {\n    class Scope{};\n};\n";
+        
+        let parser = SimpleParser::from_options(&ClassScanOptions {
+            capture_raw_matches: true,
+            ..ClassScanOptions::default()
+        });
+        let classes = parser.parse_content(content.to_string(), Path::new("raw.hpp"))?;
+        let raw: Vec<Option<&str>> = classes.iter().map(|c| c.raw_match.as_deref()).collect();
+        assert_eq!(raw, vec![
+            Some("class Rifle_Base;"),
+            Some("class Rifle : \"Rifle_Base\" "),
+            Some("class Scope{"),
+        ]);
+        
+        // Raw matches are off by default
+        let classes = SimpleParser::new(false).parse_content(content.to_string(), Path::new("raw.hpp"))?;
+        assert!(classes.iter().all(|c| c.raw_match.is_none()));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_turret_container_paths() -> Result<()> {
+        let content = r#"
+        class CfgVehicles {
+            class Tank_Base;
+            class Vehicle: Tank_Base {
+                class Turrets {
+                    class MainTurret {
+                        weapons[] = {"cannon_120mm", "mg_coax"};
+                        magazines[] = {
+                            "ammo_120mm", "ammo_120mm",
+                            "ammo_coax"
+                        };
+                        class Turrets {
+                            class CommanderOptics {
+                                gunnerOpticsModel = "\A3\optics_commander";
+                            };
+                        };
+                        class ViewOptics {};
+                    };
+                    class LoaderTurret: MainTurret {};
+                };
+                armor = 800;
+            };
+        };
+        "#;
+        
+        let parser = SimpleParser::new(false);
+        let classes = parser.parse_content(content.to_string(), Path::new("config.cpp"))?;
+        let find = |name: &str| classes.iter().find(|c| c.name == name).unwrap();
+        let path = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        
+        assert_eq!(find("Vehicle").container_path, path(&["CfgVehicles"]));
+        assert_eq!(find("Turrets").container_path, path(&["CfgVehicles", "Vehicle"]));
+        assert_eq!(find("MainTurret").container_path, path(&["CfgVehicles", "Vehicle", "Turrets"]));
+        assert_eq!(find("CommanderOptics").container_path, path(&["CfgVehicles", "Vehicle", "Turrets", "MainTurret", "Turrets"]));
+        assert_eq!(find("ViewOptics").container_path, path(&["CfgVehicles", "Vehicle", "Turrets", "MainTurret"]));
+        assert_eq!(find("LoaderTurret").container_path, path(&["CfgVehicles", "Vehicle", "Turrets"]));
+        assert_eq!(find("LoaderTurret").parent, Some("MainTurret".to_string()));
+        
+        // The turret arrays stay with MainTurret, and properties after the
+        // nested classes still belong to the vehicle
+        let main_turret = find("MainTurret");
+        assert_eq!(main_turret.properties.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), vec!["weapons", "magazines"]);
+        assert!(find("Vehicle").properties.iter().any(|(k, v)| k == "armor" && v == "800"));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_capture_declaration_metadata() -> Result<()> {
+        let content = r#"
+        class Rifle_Base;
+        class Rifle: Rifle_Base access = 3 {
+            scope = 2;
+            class Scope {};
+        };
+        class Pistol access = 1;
+        class Launcher: Rifle
+        {
+            scope = 1;
+        };
+        "#;
+        
+        let parser = SimpleParser::from_options(&ClassScanOptions {
+            capture_declaration_metadata: true,
+            ..ClassScanOptions::default()
+        });
+        let classes = parser.parse_content(content.to_string(), Path::new("weapons.hpp"))?;
+        let find = |name: &str| classes.iter().find(|c| c.name == name).unwrap();
+        
+        let names: Vec<&str> = classes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Rifle_Base", "Rifle", "Scope", "Pistol", "Launcher"]);
+        
+        let rifle = find("Rifle");
+        assert_eq!(rifle.parent.as_deref(), Some("Rifle_Base"));
+        assert_eq!(rifle.declaration_metadata.as_deref(), Some("access = 3"));
+        assert!(rifle.is_definition);
+        assert_eq!(rifle.properties, vec![("scope".to_string(), "2".to_string())]);
+        assert_eq!(find("Scope").container_path, vec!["Rifle".to_string()]);
+        
+        let pistol = find("Pistol");
+        assert_eq!(pistol.parent, None);
+        assert_eq!(pistol.declaration_metadata.as_deref(), Some("access = 1"));
+        assert!(!pistol.is_definition);
+        
+        // A body on the next line is not metadata
+        let launcher = find("Launcher");
+        assert_eq!(launcher.declaration_metadata, None);
+        assert!(launcher.is_definition);
+        assert_eq!(find("Rifle_Base").declaration_metadata, None);
+        
+        // Metadata capture is off by default
+        let classes = SimpleParser::new(false).parse_content(content.to_string(), Path::new("weapons.hpp"))?;
+        assert!(classes.iter().all(|c| c.declaration_metadata.is_none()));
+        
+        Ok(())
+    }
+    
     #[test]
     fn test_class_processor_integration() -> Result<()> {
         
@@ -137,10 +669,10 @@ mod tests {
         let mut processor = ClassProcessor::new(ClassScanOptions::default(), &output_dir);
         
         // Scan the test config file
-        let scan_result = processor.scan_specific_files(&[config_file_path.clone()])?;
+        let scan_result = processor.scan_specific_files(std::slice::from_ref(&config_file_path))?;
         
         // Verify the basic results
-        assert!(scan_result.classes.len() > 0, "Should have found some classes");
+        assert!(!scan_result.classes.is_empty(), "Should have found some classes");
         assert_eq!(scan_result.stats.total_files, 1, "Stats should show 1 file");
         
         // Check for specific classes we know should be there
@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+    use std::time::Duration;
+    use anyhow::Result;
+    use tempfile::tempdir;
+    
+    use code_scanner::class::scanner::{FileCollector, ThroughputTracker};
+    use code_scanner::utils::file_utils;
+    use code_scanner::utils::hash_utils::HashAlgorithm;
+    
+    #[test]
+    fn test_retry_recovers_from_transient_failure() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("flaky.hpp");
+        fs::write(&file_path, "class Rifle;")?;
+        
+        // Simulate a network share that fails the first read
+        let mut attempts = 0;
+        let content = file_utils::retry_with_backoff(2, Duration::from_millis(1), || {
+            attempts += 1;
+            if attempts == 1 {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "share unavailable").into());
+            }
+            file_utils::read_file_to_string(&file_path)
+        })?;
+        
+        assert_eq!(content, "class Rifle;");
+        assert_eq!(attempts, 2);
+        
+        // Permanent failures are returned without retrying
+        let mut attempts = 0;
+        let result = file_utils::retry_with_backoff(3, Duration::from_millis(1), || {
+            attempts += 1;
+            file_utils::read_file_to_string(temp_dir.path().join("missing.hpp"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+        
+        assert_eq!(file_utils::read_file_to_string_with_retry(&file_path, 1)?, "class Rifle;");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_parallel_collection_matches_serial() -> Result<()> {
+        let temp_dir = tempdir()?;
+        for (i, dir) in ["a", "a/b", "a/b/c", "d", "d/e/f/g"].iter().enumerate() {
+            let dir = temp_dir.path().join(dir);
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join(format!("config_{}.cpp", i)), "class A;")?;
+            fs::write(dir.join(format!("header_{}.HPP", i)), "class B;")?;
+            fs::write(dir.join(format!("script_{}.sqf", i)), "hint 'x';")?;
+        }
+        // Ignore files must not hide anything from the collector
+        fs::write(temp_dir.path().join(".gitignore"), "*.cpp\n")?;
+        
+        let collector = FileCollector::new();
+        let mut serial = collector.collect_files(temp_dir.path())?;
+        serial.sort();
+        let parallel = collector.collect_files_parallel(temp_dir.path())?;
+        
+        assert_eq!(parallel.len(), 10);
+        assert_eq!(parallel, serial);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_hash_algorithms() -> Result<()> {
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Xxh3] {
+            let first = algorithm.hash_string("class A {};");
+            assert_eq!(first, algorithm.hash_string("class A {};"));
+            assert_ne!(first, algorithm.hash_string("class B {};"));
+        }
+        
+        let sha = HashAlgorithm::Sha256.hash_string("class A {};");
+        let xxh = HashAlgorithm::Xxh3.hash_string("class A {};");
+        assert_eq!(sha.len(), 64);
+        assert_eq!(xxh.len(), 16);
+        assert_ne!(sha, xxh);
+        
+        let temp_dir = tempdir()?;
+        let file = temp_dir.path().join("config.cpp");
+        fs::write(&file, "class A {};")?;
+        assert_eq!(HashAlgorithm::Xxh3.hash_file(&file)?, xxh);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_throughput_tracker() -> Result<()> {
+        let mut tracker = ThroughputTracker::new(4);
+        assert_eq!(tracker.items_per_second(), None);
+        assert_eq!(tracker.eta(10), None);
+        
+        // Four items at 250ms each: 4 items per second
+        for _ in 0..4 {
+            tracker.record(Duration::from_millis(250));
+        }
+        assert_eq!(tracker.items_per_second(), Some(4.0));
+        assert_eq!(tracker.eta(8), Some(Duration::from_secs(2)));
+        
+        // Faster items push the slow ones out of the window
+        for _ in 0..4 {
+            tracker.record(Duration::from_millis(100));
+        }
+        assert_eq!(tracker.items_per_second(), Some(10.0));
+        assert_eq!(tracker.eta(5), Some(Duration::from_millis(500)));
+        
+        // A partially refreshed window averages both rates
+        tracker.record(Duration::from_millis(500));
+        assert_eq!(tracker.items_per_second(), Some(4.0 / 0.8));
+        
+        Ok(())
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_collection_reports_unreadable_paths() -> Result<()> {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+        
+        let temp_dir = tempdir()?;
+        fs::write(temp_dir.path().join("config.cpp"), "class A;")?;
+        
+        // A dangling link can't be followed, even when running as root
+        let dangling = temp_dir.path().join("missing_addon");
+        symlink(temp_dir.path().join("does_not_exist"), &dangling)?;
+        
+        let locked = temp_dir.path().join("locked");
+        fs::create_dir(&locked)?;
+        fs::write(locked.join("hidden.hpp"), "class B;")?;
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000))?;
+        let locked_readable = fs::read_dir(&locked).is_ok();
+        
+        let collection = FileCollector::new().collect_files_with_errors(temp_dir.path());
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755))?;
+        let collection = collection?;
+        
+        assert!(collection.files.contains(&temp_dir.path().join("config.cpp")));
+        assert!(collection.collection_errors.iter().any(|(path, _)| *path == dangling));
+        if !locked_readable {
+            assert!(collection.collection_errors.iter().any(|(path, _)| *path == locked));
+            assert_eq!(collection.files.len(), 1);
+        }
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_arma_defaults_collector() -> Result<()> {
+        let temp_dir = tempdir()?;
+        for file in ["config.cpp", "macros.hpp", "script_component.h", "common.inc", "description.ext", "mission.sqm", "init.sqf"] {
+            fs::write(temp_dir.path().join(file), "class A;")?;
+        }
+        
+        let mut files = FileCollector::arma_defaults().collect_files(temp_dir.path())?;
+        files.sort();
+        let names: Vec<_> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, vec!["common.inc", "config.cpp", "description.ext", "macros.hpp", "mission.sqm", "script_component.h"]);
+        
+        // The plain collector is unchanged
+        assert_eq!(FileCollector::new().collect_files(temp_dir.path())?.len(), 2);
+        
+        Ok(())
+    }
+    
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_collect_from_zip() -> Result<()> {
+        use std::io::Write;
+        use std::path::Path;
+        use code_scanner::class::scanner::SimpleParser;
+        
+        let temp_dir = tempdir()?;
+        let zip_path = temp_dir.path().join("release.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path)?);
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("addons/weapons/", options)?;
+        writer.start_file("addons/weapons/rifles.hpp", options)?;
+        writer.write_all(b"class Rifle_Base;\nclass Rifle: Rifle_Base {};\n")?;
+        writer.start_file("addons/weapons/pistols.hpp", options)?;
+        writer.write_all("\u{FEFF}class Pistol {};\n".as_bytes())?;
+        writer.start_file("README.md", options)?;
+        writer.write_all(b"class NotConfig {};")?;
+        writer.finish()?;
+        
+        let files = FileCollector::new().collect_from_zip(&zip_path)?;
+        let paths: Vec<_> = files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(paths, vec![
+            zip_path.join(Path::new("addons/weapons/rifles.hpp")),
+            zip_path.join(Path::new("addons/weapons/pistols.hpp")),
+        ]);
+        
+        let parser = SimpleParser::new(false);
+        let mut names = Vec::new();
+        for file in files {
+            for class in parser.parse_content(file.content, &file.path)? {
+                assert_eq!(class.file_path, file.path);
+                names.push(class.name);
+            }
+        }
+        assert_eq!(names, vec!["Rifle_Base", "Rifle", "Pistol"]);
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use anyhow::Result;
+    use tempfile::tempdir;
+    
+    use code_scanner::class::processor::ClassProcessor;
+    use code_scanner::class::types::{ClassScanOptions, SourceKind};
+    use code_scanner::database::{DatabaseOperations, QueryOptions};
+    use code_scanner::workflow::scan_and_update;
+    
+    #[test]
+    fn test_scan_and_update() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let input_dir = temp_dir.path().join("addons");
+        fs::create_dir_all(input_dir.join("weapons"))?;
+        fs::write(input_dir.join("config.cpp"), "class CfgVehicles {\n    class Car;\n};\n")?;
+        fs::write(input_dir.join("weapons").join("rifles.hpp"), "class Rifle_Base;\nclass Rifle: Rifle_Base {\n    scope = 2;\n};\n")?;
+        
+        let db_path = temp_dir.path().join("db").join("classes.json");
+        let stats = scan_and_update(&input_dir, &db_path, ClassScanOptions::default())?;
+        
+        assert_eq!(stats.added_classes, 4);
+        assert_eq!(stats.total_files, 2);
+        assert!(db_path.exists(), "Database should be saved");
+        assert!(!db_path.with_file_name("classes.json.tmp").exists(), "Temporary file should be renamed away");
+        
+        let db_ops = DatabaseOperations::new(&db_path)?;
+        let rifle = db_ops.get_class("Rifle").expect("Rifle should be stored");
+        assert_eq!(rifle.class.parent.as_deref(), Some("Rifle_Base"));
+        assert!(db_ops.get_class("Car").is_some());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_source_kind_tagging_and_filtering() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mission_file = temp_dir.path().join("description.ext");
+        let addon_file = temp_dir.path().join("config.cpp");
+        fs::write(&mission_file, "class CfgRespawnInventory {\n    class West_Rifleman {};\n};\n")?;
+        fs::write(&addon_file, "class CfgVehicles {\n    class Car {};\n};\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let result = processor.scan_specific_files(&[mission_file, addon_file])?;
+        assert_eq!(result.find("West_Rifleman").unwrap().source_kind, SourceKind::Mission);
+        assert_eq!(result.find("Car").unwrap().source_kind, SourceKind::Addon);
+        
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(result)?;
+        
+        let mut names: Vec<&str> = db_ops.query(&QueryOptions {
+            source_kind: Some(SourceKind::Mission),
+            ..Default::default()
+        }).iter().map(|e| e.class.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["CfgRespawnInventory", "West_Rifleman"]);
+        
+        Ok(())
+    }
+    
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_watch_directory_applies_changes() -> Result<()> {
+        use std::sync::{mpsc, Arc, Mutex};
+        use std::time::{Duration, Instant};
+        use code_scanner::workflow::{WatchDelta, watch_directory};
+        
+        let temp_dir = tempdir()?;
+        let input_dir = temp_dir.path().join("addons");
+        fs::create_dir_all(&input_dir)?;
+        let config = input_dir.join("config.cpp");
+        fs::write(&config, "class A {};\n")?;
+        
+        let mut processor = ClassProcessor::with_defaults(temp_dir.path().join("output"));
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(processor.scan_specific_files(std::slice::from_ref(&config))?)?;
+        let db_ops = Arc::new(Mutex::new(db_ops));
+        
+        let (tx, rx) = mpsc::channel::<WatchDelta>();
+        let _watcher = watch_directory(&input_dir, Arc::clone(&db_ops), processor, move |delta| {
+            let _ = tx.send(delta.clone());
+        })?;
+        
+        let wait_for = |predicate: &dyn Fn(&WatchDelta) -> bool| {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                match rx.recv_timeout(remaining) {
+                    Ok(delta) if predicate(&delta) => return Some(delta),
+                    Ok(_) => continue,
+                    Err(_) => break,
+                }
+            }
+            None
+        };
+        
+        fs::write(&config, "class A {};\nclass B: A {};\n")?;
+        let delta = wait_for(&|delta| delta.added.contains(&"B".to_string()))
+            .expect("Callback should report the added class");
+        assert_eq!(delta.file, config);
+        assert!(db_ops.lock().unwrap().get_class("B").is_some());
+        
+        fs::remove_file(&config)?;
+        let delta = wait_for(&|delta| !delta.removed.is_empty())
+            .expect("Callback should report the removed classes");
+        assert_eq!(delta.removed, vec!["A", "B"]);
+        assert!(db_ops.lock().unwrap().get_class("A").is_none());
+        
+        Ok(())
+    }
+    
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_apply_file_change_with_path_root() -> Result<()> {
+        use std::path::Path;
+        use code_scanner::workflow::apply_file_change;
+        
+        let temp_dir = tempdir()?;
+        let input_dir = temp_dir.path().join("addons");
+        fs::create_dir_all(&input_dir)?;
+        let config = input_dir.join("config.cpp");
+        fs::write(&config, "class A {};\nclass B: A {};\n")?;
+        
+        let options = ClassScanOptions {
+            path_root: Some(input_dir.clone()),
+            ..ClassScanOptions::default()
+        };
+        let mut processor = ClassProcessor::new(options, temp_dir.path().join("output"));
+        let mut db_ops = DatabaseOperations::new(temp_dir.path().join("db.json"))?;
+        db_ops.update_with_scan_results(processor.scan_specific_files(std::slice::from_ref(&config))?)?;
+        assert_eq!(db_ops.get_class("A").unwrap().class.file_path.as_deref(), Some(Path::new("config.cpp")));
+        
+        // Events carry absolute paths, while the stored ones are relative
+        fs::write(&config, "class A {};\n")?;
+        let delta = apply_file_change(&mut db_ops, &mut processor, &config)?;
+        assert_eq!(delta.removed, vec!["B"]);
+        assert!(db_ops.get_class("B").is_none());
+        
+        fs::remove_file(&config)?;
+        let delta = apply_file_change(&mut db_ops, &mut processor, &config)?;
+        assert_eq!(delta.removed, vec!["A"]);
+        assert!(db_ops.database().entries.is_empty());
+        
+        Ok(())
+    }
+}